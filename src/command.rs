@@ -0,0 +1,166 @@
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{cup_parser::FileTarget, init::Config, version, version_source::VersionSource};
+
+/// Placeholder replaced by the annotation's argument in configured commands
+pub const ARG_PLACEHOLDER: &str = "{arg}";
+
+/// How often a running command is checked for completion
+const POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+/// Reads a child pipe to the end on its own thread so the child never blocks on a full pipe
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut output = String::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_string(&mut output);
+        }
+        output
+    })
+}
+
+/// Runs a program with optional stdin input, returning its stdout or an error on timeout or non-zero exit
+pub fn run_with_timeout(
+    argv: &[String],
+    input: Option<&str>,
+    timeout: Duration,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let (program, args) = argv.split_first().ok_or("Configured command is empty")?;
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start {program}: {e}"))?;
+
+    // Write from a thread so a program that prints before reading cannot deadlock us
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        let input = input.to_string();
+        thread::spawn(move || {
+            let _ = stdin.write_all(input.as_bytes());
+        });
+    }
+
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("{program} timed out after {}s", timeout.as_secs()).into());
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+
+    if !status.success() {
+        return Err(format!("{program} exited with {status}: {}", stderr.trim()).into());
+    }
+
+    Ok(stdout)
+}
+
+/// Reads candidate versions from command output: a JSON list, or one version per line
+pub fn parse_candidates(output: &str) -> Vec<String> {
+    let trimmed = output.trim();
+    if trimmed.starts_with('[')
+        && let Ok(list) = serde_json::from_str::<Vec<serde_json::Value>>(trimmed)
+    {
+        return list
+            .into_iter()
+            .filter_map(|value| match value {
+                serde_json::Value::String(s) => Some(s),
+                serde_json::Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .collect();
+    }
+
+    trimmed
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Versions printed by a program configured in `cup.toml`
+pub struct ExternalCommandSource;
+
+impl VersionSource for ExternalCommandSource {
+    fn describe(&self, target: &FileTarget) -> String {
+        format!("command {}", target.extracted_config.tag.remote_tag)
+    }
+
+    fn list_versions(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        run_source(target, config)
+    }
+
+    fn latest(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        get_latest_tag_from_command(target, config)
+    }
+}
+
+/// Runs the configured program for the annotation and reads the versions it prints
+fn run_source(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let location = &target.extracted_config.tag.remote_tag;
+    let (name, arg) = location.split_once(' ').unwrap_or((location, ""));
+
+    let source = config
+        .command
+        .get(name)
+        .ok_or_else(|| format!("No [command.{name}] source configured in cup.toml"))?;
+
+    let argv: Vec<String> = source
+        .command
+        .iter()
+        .map(|part| part.replace(ARG_PLACEHOLDER, arg))
+        .collect();
+
+    let output = run_with_timeout(&argv, None, Duration::from_secs(source.timeout_secs))?;
+    let candidates = parse_candidates(&output);
+
+    if candidates.is_empty() {
+        return Err(format!("Command {name} printed no version for {location}").into());
+    }
+
+    Ok(candidates)
+}
+
+pub fn get_latest_tag_from_command(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let location = &target.extracted_config.tag.remote_tag;
+    let candidates = run_source(target, config)?;
+
+    version::single_or_highest(&candidates)
+        .ok_or_else(|| format!("No version among the output of {location}").into())
+}
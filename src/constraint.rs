@@ -0,0 +1,266 @@
+use crate::version::Version;
+
+/// Comparison operator of a single requirement such as `>=1.9`
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone)]
+struct Comparator {
+    op: Op,
+    version: Version,
+}
+
+impl Comparator {
+    fn matches(&self, version: &Version) -> bool {
+        match self.op {
+            Op::Eq => version.cmp(&self.version).is_eq(),
+            Op::Ne => version.cmp(&self.version).is_ne(),
+            Op::Lt => version < &self.version,
+            Op::Le => version <= &self.version,
+            Op::Gt => version > &self.version,
+            Op::Ge => version >= &self.version,
+        }
+    }
+}
+
+/// A version requirement such as `^22`, `~1.2`, `1.x` or `>=1.9,<2`, alternatives joined by `||`
+#[derive(Debug, Clone)]
+pub struct Constraint {
+    alternatives: Vec<Vec<Comparator>>,
+}
+
+/// A possibly partial version such as `1`, `1.2.x` or `1.2.3-rc.1`
+struct Partial {
+    numbers: Vec<u64>,
+    pre: Option<String>,
+}
+
+impl Partial {
+    fn parse(text: &str) -> Result<Partial, String> {
+        let text = text.trim().trim_start_matches(['v', 'V']);
+        let (core, pre) = match text.split_once('-') {
+            Some((core, pre)) => (core, Some(pre.to_string())),
+            None => (text, None),
+        };
+
+        let mut numbers = Vec::new();
+        for part in core.split('.') {
+            if matches!(part, "x" | "X" | "*" | "") {
+                break;
+            }
+            numbers.push(
+                part.parse::<u64>()
+                    .map_err(|_| format!("Invalid version {text} in constraint"))?,
+            );
+        }
+
+        Ok(Partial { numbers, pre })
+    }
+
+    fn version(&self) -> Version {
+        Version {
+            numbers: self.numbers.clone(),
+            pre: self.pre.clone(),
+        }
+    }
+
+    /// Lowest pre-release of the version itself, e.g. `2.0.0-0` for `2.0.0`
+    fn lowest_pre(&self) -> Version {
+        Version {
+            numbers: self.numbers.clone(),
+            pre: Some("0".to_string()),
+        }
+    }
+
+    /// Lowest version above every version starting with the first `len` numbers, e.g. `23.0.0-0` for `22`
+    fn bump(&self, len: usize) -> Version {
+        let mut numbers = self.numbers[..len].to_vec();
+        if let Some(last) = numbers.last_mut() {
+            *last += 1;
+        }
+        // The `-0` pre-release keeps pre-releases of the bound itself out of the range
+        Version {
+            numbers,
+            pre: Some("0".to_string()),
+        }
+    }
+
+    /// `>=self <bump(len)`, or no requirement at all for `*`
+    fn range(&self, len: usize) -> Vec<Comparator> {
+        if self.numbers.is_empty() {
+            return vec![];
+        }
+        vec![
+            Comparator {
+                op: Op::Ge,
+                version: self.version(),
+            },
+            Comparator {
+                op: Op::Lt,
+                version: self.bump(len),
+            },
+        ]
+    }
+}
+
+/// Splits a requirement into its operator and version, e.g. `>=1.9` into `>=` and `1.9`
+fn split_operator(term: &str) -> (&str, &str) {
+    const OPERATORS: [&str; 11] = [">=", "<=", "==", "!=", "~>", "~=", ">", "<", "=", "^", "~"];
+    OPERATORS
+        .iter()
+        .find_map(|op| term.strip_prefix(op).map(|rest| (*op, rest.trim())))
+        .unwrap_or(("", term))
+}
+
+/// Expands one requirement into plain comparators
+fn parse_term(term: &str) -> Result<Vec<Comparator>, String> {
+    let (op, rest) = split_operator(term);
+    if rest.is_empty() {
+        return Err(format!("Missing version after {op} in constraint"));
+    }
+    let partial = Partial::parse(rest)?;
+    let given = partial.numbers.len();
+    let exact = given >= 3 && !rest.contains(['x', 'X', '*']);
+
+    let comparator = |op: Op, version: Version| Ok(vec![Comparator { op, version }]);
+
+    match op {
+        // 1.2 and =1.2 mean any 1.2.x, a full version means exactly that version
+        "" | "=" | "==" if exact => comparator(Op::Eq, partial.version()),
+        "" | "=" | "==" => Ok(partial.range(given)),
+        "!=" => comparator(Op::Ne, partial.version()),
+        // ^1.2.3 allows changes that do not modify the left-most non-zero number
+        "^" => {
+            let len = partial
+                .numbers
+                .iter()
+                .position(|n| *n != 0)
+                .map(|i| i + 1)
+                .unwrap_or(given);
+            Ok(partial.range(len.min(given)))
+        }
+        // ~1.2.3 and ~1.2 allow patch changes, ~1 allows minor changes
+        "~" => Ok(partial.range(if given >= 2 { 2 } else { 1 })),
+        // ~>1.2 (RubyGems) and ~=1.2 (PEP 440) allow changes to the last given number only
+        "~>" | "~=" => Ok(partial.range(given.saturating_sub(1).max(1))),
+        // >1.2 means above every 1.2.x, <=1.2 includes every 1.2.x
+        ">" if !exact && given > 0 => comparator(Op::Ge, partial.bump(given)),
+        "<=" if !exact && given > 0 => comparator(Op::Lt, partial.bump(given)),
+        ">" => comparator(Op::Gt, partial.version()),
+        ">=" => comparator(Op::Ge, partial.version()),
+        // <2.0.0 keeps out 2.0.0-RC1 too, unless the bound names a pre-release itself
+        "<" if partial.pre.is_none() => comparator(Op::Lt, partial.lowest_pre()),
+        "<" => comparator(Op::Lt, partial.version()),
+        "<=" => comparator(Op::Le, partial.version()),
+        _ => Err(format!("Unknown operator {op} in constraint")),
+    }
+}
+
+impl Constraint {
+    /// Parses requirements separated by commas or spaces, alternatives separated by `||`
+    pub fn parse(input: &str) -> Result<Constraint, String> {
+        let mut alternatives = Vec::new();
+
+        for alternative in input.split("||") {
+            // Re-attach operators written apart from their version, e.g. ">= 1.9"
+            let mut terms: Vec<String> = Vec::new();
+            for token in alternative.split([',', ' ']).filter(|t| !t.is_empty()) {
+                match terms.last_mut() {
+                    Some(last) if split_operator(last).1.is_empty() => last.push_str(token),
+                    _ => terms.push(token.to_string()),
+                }
+            }
+
+            let mut comparators = Vec::new();
+            for term in &terms {
+                comparators.extend(parse_term(term).map_err(|e| format!("{e} {}", input.trim()))?);
+            }
+            alternatives.push(comparators);
+        }
+
+        Ok(Constraint { alternatives })
+    }
+
+    /// Checks whether a version satisfies every requirement of at least one alternative
+    pub fn matches(&self, version: &Version) -> bool {
+        self.alternatives
+            .iter()
+            .any(|comparators| comparators.iter().all(|c| c.matches(version)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allows(constraint: &str, version: &str) -> bool {
+        let version = Version::parse(version).expect("test versions parse");
+        Constraint::parse(constraint)
+            .expect("test constraints parse")
+            .matches(&version)
+    }
+
+    #[test]
+    fn less_than_excludes_pre_releases_of_the_bound() {
+        assert!(allows(">=1.9,<2", "1.9.25"));
+        assert!(!allows(">=1.9,<2", "2.0.0-RC1"));
+        assert!(!allows("<2.0.0", "2.0.0-beta.1"));
+        assert!(allows("<2.0.0", "1.99.0-rc.1"));
+        assert!(allows("<2.0.0-rc.2", "2.0.0-rc.1"));
+        assert!(!allows("<2.0.0-rc.2", "2.0.0-rc.2"));
+    }
+
+    #[test]
+    fn less_or_equal_includes_the_whole_partial_version() {
+        assert!(allows("<=1.2", "1.2.9"));
+        assert!(!allows("<=1.2", "1.3.0"));
+        assert!(!allows("<=1.2", "1.3.0-alpha"));
+        assert!(allows("<=1.2.3", "1.2.3"));
+        assert!(!allows("<=1.2.3", "1.2.4"));
+    }
+
+    #[test]
+    fn caret_on_zero_major_fixes_the_first_non_zero_number() {
+        assert!(allows("^0.3", "0.3.9"));
+        assert!(!allows("^0.3", "0.4.0"));
+        assert!(allows("^0.0.3", "0.0.3"));
+        assert!(!allows("^0.0.3", "0.0.4"));
+        assert!(allows("^1.2", "1.9.0"));
+        assert!(!allows("^1.2", "2.0.0"));
+    }
+
+    #[test]
+    fn tilde_allows_patch_changes_or_minor_changes_for_a_major() {
+        assert!(allows("~1.2.3", "1.2.9"));
+        assert!(!allows("~1.2.3", "1.3.0"));
+        assert!(!allows("~1.2.3", "1.2.2"));
+        assert!(allows("~1", "1.9.0"));
+        assert!(!allows("~1", "2.0.0"));
+    }
+
+    #[test]
+    fn wildcards_match_any_number_in_their_place() {
+        assert!(allows("1.x", "1.7.3"));
+        assert!(!allows("1.x", "2.0.0"));
+        assert!(allows("1.2.*", "1.2.8"));
+        assert!(!allows("1.2.*", "1.3.0"));
+        assert!(allows("*", "42.0.0"));
+    }
+
+    #[test]
+    fn operators_apart_from_their_version_and_alternatives() {
+        assert!(allows(">= 22 < 23", "22.11.0"));
+        assert!(!allows(">= 22 < 23", "23.0.0"));
+        assert!(allows("~20.1 || ^22", "20.1.5"));
+        assert!(allows("~20.1 || ^22", "22.3.0"));
+        assert!(!allows("~20.1 || ^22", "21.0.0"));
+        assert!(Constraint::parse("~>").is_err());
+    }
+}
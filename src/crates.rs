@@ -0,0 +1,125 @@
+use serde::Deserialize;
+
+use crate::{cup_parser::FileTarget, http, init::Config, version, version_source::VersionSource};
+
+/// Environment variable holding the token for an authenticated private registry
+pub const CARGO_REGISTRY_TOKEN_ENV: &str = "CARGO_REGISTRY_TOKEN";
+
+/// One line of a sparse index file, describing a single published version
+#[derive(Debug, Deserialize)]
+struct IndexEntry {
+    vers: String,
+    #[serde(default)]
+    yanked: bool,
+}
+
+/// Returns the index path of a crate, e.g. `se/rd/serde` or `3/s/syn`
+///
+/// `None` when the name is not a valid crate name, which only allows ASCII
+/// alphanumerics, `-` and `_`
+fn index_path(name: &str) -> Option<String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return None;
+    }
+
+    let name = name.to_ascii_lowercase();
+    Some(match name.len() {
+        1 => format!("1/{name}"),
+        2 => format!("2/{name}"),
+        3 => format!("3/{}/{name}", &name[..1]),
+        _ => format!("{}/{}/{name}", &name[..2], &name[2..4]),
+    })
+}
+
+/// Versions published to crates.io or a sparse registry
+pub struct CratesSource;
+
+impl VersionSource for CratesSource {
+    fn describe(&self, target: &FileTarget) -> String {
+        format!("crate {}", target.extracted_config.tag.remote_tag)
+    }
+
+    fn list_versions(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        list_published_versions(target, config)
+    }
+
+    fn latest(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        get_latest_tag_from_crates(target, config)
+    }
+}
+
+/// Lists every non-yanked version from the crate's index file
+fn list_published_versions(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let name = &target.extracted_config.tag.remote_tag;
+    let path = index_path(name).ok_or_else(|| format!("Invalid crate name {name}"))?;
+    let url = format!("{}/{path}", config.crates.index_base());
+
+    let mut headers = vec![];
+    if !config.crates.is_crates_io()
+        && let Ok(token) = std::env::var(CARGO_REGISTRY_TOKEN_ENV)
+    {
+        headers.push(("Authorization", token));
+    }
+
+    let body = http::get(&url, &headers)?
+        .ok_or_else(|| format!("Crate {name} not found in {}", config.crates.index_base()))?
+        .into_string()?;
+
+    Ok(body
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str::<IndexEntry>)
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .filter(|entry| !entry.yanked)
+        .map(|entry| entry.vers)
+        .collect())
+}
+
+pub fn get_latest_tag_from_crates(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let name = &target.extracted_config.tag.remote_tag;
+    let versions = list_published_versions(target, config)?;
+
+    version::highest_stable(&versions)
+        .map(str::to_string)
+        .ok_or_else(|| format!("No stable, non-yanked versions found for crate {name}").into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_path_by_name_length() {
+        assert_eq!(index_path("a").as_deref(), Some("1/a"));
+        assert_eq!(index_path("cc").as_deref(), Some("2/cc"));
+        assert_eq!(index_path("syn").as_deref(), Some("3/s/syn"));
+        assert_eq!(index_path("Serde").as_deref(), Some("se/rd/serde"));
+    }
+
+    #[test]
+    fn index_path_rejects_invalid_names() {
+        assert_eq!(index_path(""), None);
+        assert_eq!(index_path("sérde"), None);
+        assert_eq!(index_path("日本語"), None);
+        assert_eq!(index_path("foo/bar"), None);
+    }
+}
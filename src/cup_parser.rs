@@ -1,16 +1,135 @@
+use std::collections::HashMap;
+
 use crate::{
     file_finder::FileInfo,
+    helm::APP_VERSION_FIELD,
     init::{Config, Remote, Tag, Target},
 };
 
 /// The default comment pattern to search for in files
 pub const CUP_COMMENT: &str = "[cup]";
 
+/// Option key a bare requirement such as `^22` is stored under
+pub const CONSTRAINT_OPTION: &str = "constraint";
+
+/// Option key limiting how far a target may move, e.g. `update=patch`
+pub const UPDATE_OPTION: &str = "update";
+
+/// Option key accepting any pre-release, e.g. `prerelease=true`
+pub const PRERELEASE_OPTION: &str = "prerelease";
+
+/// Option key accepting pre-releases down to a channel, e.g. `channel=beta`
+pub const CHANNEL_OPTION: &str = "channel";
+
+/// Option key selecting a family of tags by regex, e.g. `tag="gson-parent-(.*)"`
+pub const TAG_OPTION: &str = "tag";
+
+/// Option key selecting a family of tags by a literal prefix, e.g. `prefix=dagger-`
+pub const PREFIX_OPTION: &str = "prefix";
+
+/// Option key formatting the written value, e.g. `template=v{major}.{minor}.{patch}`
+pub const TEMPLATE_OPTION: &str = "template";
+
+/// Option key holding back versions published too recently, e.g. `min_age=3d`
+pub const MIN_AGE_OPTION: &str = "min_age";
+
+/// Option key naming versions never to pick, e.g. `skip=5.3.0` or `skip="5.3.0 || 5.4.0"`
+pub const SKIP_OPTION: &str = "skip";
+
 /// Represents a target found in a file with cup comment configuration
 pub struct FileTarget {
     pub file: FileInfo,
     pub row: i128,
     pub extracted_config: Target,
+    /// `key=value` options written after the location, e.g. `constraint=">=1.9,<2"`
+    pub options: HashMap<String, String>,
+}
+
+/// Splits annotation words on whitespace, keeping double-quoted option values such as
+/// `key="a, b"` together; quotes anywhere else are part of the word
+fn split_words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+
+    for c in text.chars() {
+        match c {
+            '"' if quoted => quoted = false,
+            '"' if current.strip_suffix('=').is_some_and(is_option_key) => quoted = true,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    words.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+
+    words
+}
+
+/// Length of a `<path>#/regex/` reference at the start of `text`, through the slash closing the
+/// regex, which may hold spaces and `=`, e.g. `.github/versions.yml#/node-version: (.*)/`
+fn pattern_reference_len(text: &str) -> Option<usize> {
+    let start = text.find("#/")?;
+    if text[..start].contains(char::is_whitespace) {
+        return None;
+    }
+
+    let pattern = start + 2;
+    text[pattern..]
+        .match_indices('/')
+        .map(|(i, _)| pattern + i + 1)
+        .find(|&end| text[end..].chars().next().is_none_or(char::is_whitespace))
+}
+
+/// Checks for an option key such as `constraint` or `min-age`
+fn is_option_key(key: &str) -> bool {
+    key.starts_with(|c: char| c.is_ascii_alphabetic())
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Separates `key=value` options and a bare requirement (`^22`, `~1.2`, `>=1.9`) from the
+/// remote and location words of an annotation
+fn extract_options(text: &str) -> (String, HashMap<String, String>) {
+    let mut options = HashMap::new();
+    let mut rest = Vec::new();
+    // Set after an operator written apart from its version, e.g. the `>=` of `>= 22`
+    let mut awaiting_version = false;
+
+    for word in split_words(text) {
+        let is_operator = word
+            .trim_start_matches(['^', '~', '<', '>', '=', '!'])
+            .is_empty();
+        let continues_requirement = std::mem::replace(&mut awaiting_version, is_operator);
+
+        match word.split_once('=') {
+            Some((key, value)) if is_option_key(key) => {
+                options.insert(key.to_string(), value.to_string());
+            }
+            // Consecutive requirements form one constraint, e.g. `>=1.9 <2`, `>= 22 < 23` or `^1 || ^2`
+            _ if word.starts_with(['^', '~', '<', '>', '='])
+                || word == "||"
+                || continues_requirement =>
+            {
+                options
+                    .entry(CONSTRAINT_OPTION.to_string())
+                    .and_modify(|constraint: &mut String| {
+                        constraint.push(' ');
+                        constraint.push_str(&word);
+                    })
+                    .or_insert(word);
+            }
+            _ => rest.push(word),
+        }
+    }
+
+    (rest.join(" "), options)
 }
 
 /// Parses a line containing a cup comment and extracts target configuration
@@ -37,53 +156,161 @@ pub fn parse_cup_line(
     // Find the position of [cup] comment
     let cup_pos = line.find(CUP_COMMENT)?;
 
-    // Extract the part after [cup]
-    let after_cup = &line[cup_pos + CUP_COMMENT.len()..].trim();
+    // Extract the part after [cup], setting its options aside; a `File` regex is kept whole
+    let annotation = line[cup_pos + CUP_COMMENT.len()..].trim();
+
+    // A quoted marker, such as `cup_pattern = "[cup]"` in cup.toml, is not an annotation
+    if annotation.starts_with(['"', '\'']) {
+        return None;
+    }
+    let file_part = match annotation.split_whitespace().next() {
+        Some("File") => annotation.strip_prefix("File"),
+        Some(first) if Remote::from_name(first, config).is_none() => {
+            (config.remote_default == "File").then_some(annotation)
+        }
+        _ => None,
+    };
+    let file_pattern = file_part
+        .map(str::trim_start)
+        .and_then(|file_part| Some(file_part.split_at(pattern_reference_len(file_part)?)));
+    let (after_cup, options) = match file_pattern {
+        Some((reference, rest)) => (format!("File {reference}"), extract_options(rest).1),
+        None => extract_options(annotation),
+    };
 
-    // Determine the remote type and owner/repo
-    let (remote_type, owner_repo) = if after_cup.starts_with("GitHub") {
-        // Explicit GitHub type specified
-        let github_part = after_cup.strip_prefix("GitHub")?.trim();
-        let owner_repo = github_part.split_whitespace().next()?;
+    let mut target = parse_annotation(file_info, &after_cup, row, config)?;
 
-        // Validate that owner_repo contains a slash and valid format
-        if !owner_repo.contains('/') || owner_repo.len() < 3 {
-            return None;
+    // Locations end up as program arguments, never let one pass for an option
+    if target.extracted_config.tag.remote_tag.starts_with('-') {
+        return None;
+    }
+
+    target.options = options;
+    Some(target)
+}
+
+/// Determines the remote and location of an annotation whose options were removed
+fn parse_annotation(
+    file_info: &FileInfo,
+    after_cup: &str,
+    row: i128,
+    config: &Config,
+) -> Option<FileTarget> {
+    // The first word names the remote only when it matches one exactly, so locations such as
+    // `HttpToolkit/httptoolkit-server` still fall back to remote_default
+    let first_word = after_cup.split_whitespace().next()?;
+    match Remote::from_name(first_word, config) {
+        Some(remote) => {
+            let location = after_cup.trim_start().strip_prefix(first_word)?.trim();
+            parse_location(file_info, row, remote, location)
+        }
+        None => {
+            let remote = Remote::from_name(&config.remote_default, config)?;
+            parse_location(file_info, row, remote, after_cup.trim())
         }
+    }
+}
 
-        (Remote::GitHub, owner_repo)
-    } else if !after_cup.is_empty() {
-        // No explicit type, use remote_default and treat the whole string as owner/repo
-        let owner_repo = after_cup.split_whitespace().next()?;
+/// Validates and normalizes the location written after a remote's name
+fn parse_location(
+    file_info: &FileInfo,
+    row: i128,
+    remote: Remote,
+    location: &str,
+) -> Option<FileTarget> {
+    let mut parts = location.split_whitespace();
+    let first = parts.next()?;
 
-        // Validate that owner_repo contains a slash and valid format
-        if !owner_repo.contains('/') || owner_repo.len() < 3 {
-            return None;
+    let location = match &remote {
+        // A registered plugin receives everything after its name as arguments
+        Remote::Plugin(_) => location.split_whitespace().collect::<Vec<_>>().join(" "),
+        // Project paths of GitHub and GitLab may contain nested groups, git URLs a path
+        Remote::GitHub | Remote::GitLab | Remote::Git | Remote::Terraform | Remote::Packagist => {
+            // Validate that the location contains a slash and valid format
+            if !first.contains('/') || first.len() < 3 {
+                return None;
+            }
+            first.to_string()
+        }
+        Remote::Maven => {
+            // Validate that coordinates contain a group and an artifact
+            if !first.contains(':') || first.len() < 3 {
+                return None;
+            }
+            first.to_string()
+        }
+        // Image, crate, package, project, module or gem name
+        Remote::Oci
+        | Remote::Crates
+        | Remote::Npm
+        | Remote::PyPI
+        | Remote::GoProxy
+        | Remote::RubyGems
+        | Remote::NuGet => first.to_string(),
+        // Repository URL and chart name, optionally `appVersion`
+        Remote::Helm => {
+            let mut location = first.to_string();
+            if let Some(chart) = parts.next() {
+                location.push(' ');
+                location.push_str(chart);
+                if parts.next() == Some(APP_VERSION_FIELD) {
+                    location.push(' ');
+                    location.push_str(APP_VERSION_FIELD);
+                }
+            }
+            location
         }
+        // A URL or named source, optionally followed by a selector
+        Remote::Http => match parts.next() {
+            Some(selector) if selector.starts_with(['$', '.', '[']) => {
+                format!("{first} {selector}")
+            }
+            _ => first.to_string(),
+        },
+        // A named [command.<name>] source and its argument
+        Remote::Command => match parts.next() {
+            Some(arg) => format!("{first} {arg}"),
+            None => first.to_string(),
+        },
+        // <path>#<key|L<line>|/regex/>, the regex may hold spaces
+        Remote::File => {
+            let reference = match pattern_reference_len(location) {
+                Some(len) => &location[..len],
+                None => first,
+            };
 
-        match config.remote_default.as_str() {
-            "GitHub" => (Remote::GitHub, owner_repo),
-            // Add more cases here when more remote types are supported
-            _ => (Remote::GitHub, owner_repo), // fallback to GitHub for unknown defaults
+            // Validate that the reference names what to read
+            if !reference.contains('#') {
+                return None;
+            }
+            reference.to_string()
         }
-    } else {
-        // Empty after [cup], nothing to parse
-        return None;
     };
 
+    Some(build_file_target(file_info, row, remote, &location))
+}
+
+/// Creates the target for an annotated line once remote type and location are known
+fn build_file_target(
+    file_info: &FileInfo,
+    row: i128,
+    remote_type: Remote,
+    location: &str,
+) -> FileTarget {
     let target = Target {
         name: format!("{}:{}", file_info.full_path.display(), row + 1),
         tag: Tag {
-            remote_tag: owner_repo.to_string(),
+            remote_tag: location.to_string(),
             remote_type,
         },
     };
 
-    Some(FileTarget {
+    FileTarget {
         file: file_info.clone(),
         row,
         extracted_config: target,
-    })
+        options: HashMap::new(),
+    }
 }
 
 /// Searches through all files and extracts targets with cup comments
@@ -107,3 +334,65 @@ pub fn find_cup_targets(files: &[FileInfo], config: &Config) -> Vec<FileTarget>
 
     targets
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn parse(line: &str, remote_default: &str) -> Option<(Remote, String)> {
+        let file = FileInfo {
+            full_path: PathBuf::from("./versions.toml"),
+            filename: "versions.toml".to_string(),
+            file_type: "toml".to_string(),
+            content: String::new(),
+        };
+        let config = Config {
+            remote_default: remote_default.to_string(),
+            ..Config::default()
+        };
+        let target = parse_cup_line(&file, line, 0, &config)?;
+        let tag = target.extracted_config.tag;
+        Some((tag.remote_type, tag.remote_tag))
+    }
+
+    #[test]
+    fn locations_starting_with_a_remote_name_use_the_default() {
+        for location in [
+            "Filestack/filestack-js",
+            "HttpToolkit/httptoolkit-server",
+            "GitHubDesktop/desktop",
+            "Commander-js/commander",
+            "Helmfile/helmfile",
+        ] {
+            let line = format!(r#"v = "1.0.0" # [cup] {location}"#);
+            assert_eq!(
+                parse(&line, "GitHub"),
+                Some((Remote::GitHub, location.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn explicit_remotes_are_whole_words() {
+        assert_eq!(
+            parse(
+                r#"v = "1.0.0" # [cup] File tools.yml#/node: (.*)/ update=minor"#,
+                "GitHub"
+            ),
+            Some((Remote::File, "tools.yml#/node: (.*)/".to_string()))
+        );
+        assert_eq!(
+            parse(
+                r#"v = "1.0.0" # [cup] Git https://example.com/repo.git"#,
+                "GitHub"
+            ),
+            Some((Remote::Git, "https://example.com/repo.git".to_string()))
+        );
+        assert_eq!(
+            parse(r#"v = "1.0" # [cup] serde"#, "Crates"),
+            Some((Remote::Crates, "serde".to_string()))
+        );
+        assert_eq!(parse(r#"v = "1.0" # [cup] Filestack"#, "GitHub"), None);
+    }
+}
@@ -0,0 +1,146 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use crate::{cup_parser::FileTarget, init::Config, version_source::VersionSource};
+
+// First version-looking token on a referenced line, e.g. 22.11.0 or 1.2.3-rc.1
+static LINE_VERSION_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"v?[0-9]+(?:\.[0-9]+)+(?:-[0-9A-Za-z][0-9A-Za-z.\-]*)?")
+        .expect("Failed to compile line version regex")
+});
+
+/// What to read from the referenced file
+#[derive(Debug, PartialEq)]
+enum Selector<'a> {
+    /// `#tools.node`: a dotted key in a TOML, JSON or YAML document
+    Key(&'a str),
+    /// `#L12`: the first version on a 1-based line
+    Line(usize),
+    /// `#/node-version: (.*)/`: the first capture group (or whole match) of a regex
+    Pattern(&'a str),
+}
+
+fn parse_reference(reference: &str) -> Result<(&str, Selector<'_>), String> {
+    let (path, fragment) = reference
+        .split_once('#')
+        .ok_or_else(|| format!("Expected <path>#<key|L<line>|/regex/>, got {reference}"))?;
+
+    let selector = if let Some(pattern) = fragment
+        .strip_prefix('/')
+        .and_then(|rest| rest.strip_suffix('/'))
+    {
+        Selector::Pattern(pattern)
+    } else if let Some(line) = fragment
+        .strip_prefix('L')
+        .and_then(|n| n.parse::<usize>().ok())
+    {
+        Selector::Line(line)
+    } else {
+        Selector::Key(fragment)
+    };
+
+    Ok((path, selector))
+}
+
+/// Resolves the referenced path relative to the directory of the annotated file
+fn resolve_path(annotated: &Path, path: &str) -> PathBuf {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    annotated
+        .parent()
+        .map(|dir| dir.join(path))
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// Parses a document into a JSON value based on the file extension
+fn parse_document(path: &Path, content: &str) -> Result<Value, Box<dyn std::error::Error>> {
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    match extension {
+        "toml" => Ok(serde_json::to_value(toml::from_str::<toml::Value>(
+            content,
+        )?)?),
+        "json" => Ok(serde_json::from_str(content)?),
+        "yaml" | "yml" => Ok(serde_yaml::from_str(content)?),
+        _ => Err(format!(
+            "Cannot look up keys in {}, use a #L<line> or #/regex/ reference instead",
+            path.display()
+        )
+        .into()),
+    }
+}
+
+fn lookup_key(document: &Value, key: &str) -> Option<String> {
+    let value = key
+        .split('.')
+        .try_fold(document, |value, part| match value {
+            Value::Object(map) => map.get(part),
+            Value::Array(items) => part.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        })?;
+
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// A version defined in another file of the repository
+pub struct FileSource;
+
+impl VersionSource for FileSource {
+    fn describe(&self, target: &FileTarget) -> String {
+        format!("file {}", target.extracted_config.tag.remote_tag)
+    }
+
+    fn list_versions(
+        &self,
+        target: &FileTarget,
+        _config: &Config,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        Ok(vec![get_latest_tag_from_file(target)?])
+    }
+
+    fn latest(
+        &self,
+        target: &FileTarget,
+        _config: &Config,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        get_latest_tag_from_file(target)
+    }
+}
+
+pub fn get_latest_tag_from_file(target: &FileTarget) -> Result<String, Box<dyn std::error::Error>> {
+    let reference = &target.extracted_config.tag.remote_tag;
+    let (path, selector) = parse_reference(reference)?;
+    let path = resolve_path(&target.file.full_path, path);
+
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+
+    let found = match selector {
+        Selector::Key(key) => lookup_key(&parse_document(&path, &content)?, key),
+        Selector::Line(line) => content
+            .lines()
+            .nth(line.saturating_sub(1))
+            .and_then(|text| LINE_VERSION_RE.find(text))
+            .map(|m| m.as_str().to_string()),
+        Selector::Pattern(pattern) => {
+            let regex = Regex::new(pattern)?;
+            regex.captures(&content).map(|caps| {
+                caps.get(1)
+                    .or_else(|| caps.get(0))
+                    .map(|m| m.as_str().to_string())
+                    .unwrap_or_default()
+            })
+        }
+    };
+
+    found
+        .filter(|version| !version.is_empty())
+        .ok_or_else(|| format!("No version found for {reference} in {}", path.display()).into())
+}
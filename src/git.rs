@@ -0,0 +1,65 @@
+use crate::{cup_parser::FileTarget, init::Config, version, version_source::VersionSource};
+
+/// Suffix git appends to the peeled commit of an annotated tag
+const PEELED_SUFFIX: &str = "^{}";
+
+/// Extracts tag names from `git ls-remote --tags` output, skipping peeled refs
+fn parse_ls_remote(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.split('\t').nth(1))
+        .filter(|reference| !reference.ends_with(PEELED_SUFFIX))
+        .filter_map(|reference| reference.strip_prefix("refs/tags/"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Tags of any git repository, listed with `git ls-remote`
+pub struct GitSource;
+
+impl VersionSource for GitSource {
+    fn describe(&self, target: &FileTarget) -> String {
+        format!("git repository {}", target.extracted_config.tag.remote_tag)
+    }
+
+    fn list_versions(
+        &self,
+        target: &FileTarget,
+        _config: &Config,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        list_tags(target)
+    }
+
+    fn latest(
+        &self,
+        target: &FileTarget,
+        _config: &Config,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        get_latest_tag_from_git(target)
+    }
+}
+
+fn list_tags(target: &FileTarget) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let sh = xshell::Shell::new()?;
+    let url = target.extracted_config.tag.remote_tag.clone();
+
+    // `--` keeps a location such as `--upload-pack=...` from being read as an option
+    let res = xshell::cmd!(sh, "git ls-remote --tags -- {url}")
+        .quiet()
+        .read()
+        .map_err(|e| format!("Failed to list tags for {url}: {e}"))?;
+
+    Ok(parse_ls_remote(&res))
+}
+
+pub fn get_latest_tag_from_git(target: &FileTarget) -> Result<String, Box<dyn std::error::Error>> {
+    let url = &target.extracted_config.tag.remote_tag;
+    let tags = list_tags(target)?;
+    if tags.is_empty() {
+        return Err(format!("No tags found for repository {url}").into());
+    }
+
+    version::highest_stable(&tags)
+        .map(str::to_string)
+        .ok_or_else(|| format!("No stable version tags found for repository {url}").into())
+}
@@ -0,0 +1,226 @@
+use serde::Deserialize;
+
+use crate::{
+    cup_parser::FileTarget,
+    http,
+    init::Config,
+    version,
+    version_source::{Release, VersionSource},
+};
+
+/// Environment variables checked, in order, for a GitHub token
+pub const GITHUB_TOKEN_ENVS: [&str; 2] = ["GITHUB_TOKEN", "GH_TOKEN"];
+
+#[derive(Debug, Deserialize)]
+struct LatestTag {
+    #[serde(alias = "tagName")]
+    tag_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubTag {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubRelease {
+    tag_name: String,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    published_at: Option<String>,
+}
+
+/// Releases of a GitHub repository, falling back to its tags
+pub struct GitHubSource;
+
+impl VersionSource for GitHubSource {
+    fn describe(&self, target: &FileTarget) -> String {
+        format!(
+            "GitHub repository {}",
+            target.extracted_config.tag.remote_tag
+        )
+    }
+
+    fn list_versions(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        Ok(self
+            .list_releases(target, config)?
+            .into_iter()
+            .map(|release| release.tag)
+            .collect())
+    }
+
+    fn list_releases(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+        match list_releases_from_api(target, config) {
+            Ok(releases) => Ok(releases),
+            Err(e) if config.github.gh_fallback => {
+                eprintln!("GitHub API lookup failed ({e}), falling back to gh CLI");
+                let tags = list_tags_from_gh_cli(target)?;
+                Ok(tags
+                    .into_iter()
+                    .map(|tag| Release::new(tag, None))
+                    .collect())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn latest(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        get_latest_tag_from_github(target, config)
+    }
+}
+
+fn request_headers() -> Vec<(&'static str, String)> {
+    let mut headers = vec![
+        ("Accept", "application/vnd.github+json".to_string()),
+        ("X-GitHub-Api-Version", "2022-11-28".to_string()),
+    ];
+
+    let token = GITHUB_TOKEN_ENVS
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|token| !token.is_empty());
+    if let Some(token) = token {
+        headers.push(("Authorization", format!("Bearer {token}")));
+    }
+
+    headers
+}
+
+pub fn get_latest_tag_from_github(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<String, Box<dyn std::error::Error>> {
+    match get_latest_tag_from_api(target, config) {
+        Ok(tag) => Ok(tag),
+        Err(e) if config.github.gh_fallback => {
+            eprintln!("GitHub API lookup failed ({e}), falling back to gh CLI");
+            get_latest_tag_from_gh_cli(target)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn get_latest_tag_from_api(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let owner_and_repo = &target.extracted_config.tag.remote_tag;
+    let api = format!("{}/repos/{owner_and_repo}", config.github.api_base());
+    let headers = request_headers();
+
+    // First try to get the latest release
+    let release_url = format!("{api}/releases/latest");
+    // A release not flagged as pre-release can still carry an `-rc` tag
+    if let Some(release) = http::get_json::<LatestTag>(&release_url, &headers)?
+        && !version::is_prerelease(&release.tag_name)
+    {
+        return Ok(release.tag_name);
+    }
+
+    // If release fails, try to get the highest tag
+    println!("release not found");
+    let tags_url = format!("{api}/tags?per_page=100");
+    let tags: Vec<String> = http::get_json_pages::<GitHubTag>(&tags_url, &headers)?
+        .ok_or_else(|| format!("GitHub repository {owner_and_repo} not found"))?
+        .into_iter()
+        .map(|tag| tag.name)
+        .collect();
+
+    version::highest_stable_tag(&tags)
+        .ok_or_else(|| format!("No stable tags found for repository {owner_and_repo}").into())
+}
+
+fn get_latest_tag_from_gh_cli(target: &FileTarget) -> Result<String, Box<dyn std::error::Error>> {
+    let sh = xshell::Shell::new()?;
+    let owner_and_repo = target.extracted_config.tag.remote_tag.clone();
+
+    // First try to get the latest release
+    if let Ok(res) =
+        xshell::cmd!(sh, "gh release view --repo {owner_and_repo} --json tagName").read()
+        && let Ok(tag_name) = serde_json::from_str::<LatestTag>(&res)
+        && !version::is_prerelease(&tag_name.tag_name)
+    {
+        return Ok(tag_name.tag_name);
+    }
+
+    // If release fails, try to get the highest tag
+    println!("release not found");
+    let res = xshell::cmd!(
+        sh,
+        "gh api --paginate repos/{owner_and_repo}/tags --jq '.[].name'"
+    )
+    .read()
+    .map_err(|e| format!("Failed to get tags for {owner_and_repo}: {e}"))?;
+
+    let tags: Vec<String> = res.lines().map(str::to_string).collect();
+    version::highest_stable_tag(&tags)
+        .ok_or_else(|| format!("No stable tags found for repository {owner_and_repo}").into())
+}
+
+/// Lists published releases with their publish times, or all tags without times when the
+/// repository has no releases
+fn list_releases_from_api(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+    let owner_and_repo = &target.extracted_config.tag.remote_tag;
+    let api = format!("{}/repos/{owner_and_repo}", config.github.api_base());
+    let headers = request_headers();
+
+    let releases_url = format!("{api}/releases?per_page=100");
+    let releases = http::get_json_pages::<GitHubRelease>(&releases_url, &headers)?
+        .ok_or_else(|| format!("GitHub repository {owner_and_repo} not found"))?;
+    let releases: Vec<Release> = releases
+        .into_iter()
+        .filter(|release| !release.draft)
+        .map(|release| Release::new(release.tag_name, release.published_at.as_deref()))
+        .collect();
+    if !releases.is_empty() {
+        return Ok(releases);
+    }
+
+    let tags_url = format!("{api}/tags?per_page=100");
+    Ok(http::get_json_pages::<GitHubTag>(&tags_url, &headers)?
+        .ok_or_else(|| format!("GitHub repository {owner_and_repo} not found"))?
+        .into_iter()
+        .map(|tag| Release::new(tag.name, None))
+        .collect())
+}
+
+fn list_tags_from_gh_cli(target: &FileTarget) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let sh = xshell::Shell::new()?;
+    let owner_and_repo = target.extracted_config.tag.remote_tag.clone();
+
+    let releases = xshell::cmd!(
+        sh,
+        "gh api --paginate repos/{owner_and_repo}/releases --jq '.[] | select(.draft | not) | .tag_name'"
+    )
+    .read()
+    .map_err(|e| format!("Failed to get releases for {owner_and_repo}: {e}"))?;
+    let tags: Vec<String> = releases.lines().map(str::to_string).collect();
+    if !tags.is_empty() {
+        return Ok(tags);
+    }
+
+    let res = xshell::cmd!(
+        sh,
+        "gh api --paginate repos/{owner_and_repo}/tags --jq '.[].name'"
+    )
+    .read()
+    .map_err(|e| format!("Failed to get tags for {owner_and_repo}: {e}"))?;
+
+    Ok(res.lines().map(str::to_string).collect())
+}
@@ -0,0 +1,130 @@
+use serde::Deserialize;
+
+use crate::{
+    cup_parser::FileTarget,
+    http,
+    init::Config,
+    version,
+    version_source::{Release, VersionSource},
+};
+
+/// Environment variable holding a GitLab personal, project or group access token
+pub const GITLAB_TOKEN_ENV: &str = "GITLAB_TOKEN";
+
+#[derive(Debug, Deserialize)]
+struct GitLabRelease {
+    tag_name: String,
+    #[serde(default)]
+    released_at: Option<String>,
+    /// Releases dated in the future are announced but not out yet
+    #[serde(default)]
+    upcoming_release: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitLabTag {
+    name: String,
+}
+
+/// Encodes a project path such as `group/subgroup/project` into the
+/// URL-encoded form GitLab expects in place of a numeric project id
+fn encode_project_path(path: &str) -> String {
+    path.trim_matches('/').replace('/', "%2F")
+}
+
+fn auth_headers() -> Vec<(&'static str, String)> {
+    match std::env::var(GITLAB_TOKEN_ENV) {
+        Ok(token) if !token.is_empty() => vec![("PRIVATE-TOKEN", token)],
+        _ => vec![],
+    }
+}
+
+/// Releases of a GitLab project, falling back to its tags
+pub struct GitLabSource;
+
+impl VersionSource for GitLabSource {
+    fn describe(&self, target: &FileTarget) -> String {
+        format!("GitLab project {}", target.extracted_config.tag.remote_tag)
+    }
+
+    fn list_versions(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        Ok(self
+            .list_releases(target, config)?
+            .into_iter()
+            .map(|release| release.tag)
+            .collect())
+    }
+
+    /// Lists releases with the time they were released, or the project's tags when it has none
+    fn list_releases(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+        let project_path = &target.extracted_config.tag.remote_tag;
+        let project = encode_project_path(project_path);
+        let api = format!("{}/api/v4/projects/{project}", config.gitlab.api_base());
+        let headers = auth_headers();
+
+        let releases_url = format!("{api}/releases?per_page=100");
+        let releases: Vec<Release> =
+            http::get_json_pages::<GitLabRelease>(&releases_url, &headers)?
+                .ok_or_else(|| format!("GitLab project {project_path} not found"))?
+                .into_iter()
+                .filter(|release| !release.upcoming_release)
+                .map(|release| Release::new(release.tag_name, release.released_at.as_deref()))
+                .collect();
+        if !releases.is_empty() {
+            return Ok(releases);
+        }
+
+        let tags_url = format!("{api}/repository/tags?order_by=version&sort=desc&per_page=100");
+        Ok(http::get_json_pages::<GitLabTag>(&tags_url, &headers)?
+            .ok_or_else(|| format!("GitLab project {project_path} not found"))?
+            .into_iter()
+            .map(|tag| Release::new(tag.name, None))
+            .collect())
+    }
+
+    fn latest(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        get_latest_tag_from_gitlab(target, config)
+    }
+}
+
+pub fn get_latest_tag_from_gitlab(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let project_path = &target.extracted_config.tag.remote_tag;
+    let project = encode_project_path(project_path);
+    let api = format!("{}/api/v4/projects/{project}", config.gitlab.api_base());
+    let headers = auth_headers();
+
+    // First try to get the latest release, unless it is tagged as a pre-release
+    let release_url = format!("{api}/releases/permalink/latest");
+    if let Some(release) = http::get_json::<GitLabRelease>(&release_url, &headers)?
+        && !version::is_prerelease(&release.tag_name)
+    {
+        return Ok(release.tag_name);
+    }
+
+    // Otherwise fall back to the highest stable tag
+    println!("release not found");
+    let tags_url = format!("{api}/repository/tags?order_by=version&sort=desc&per_page=100");
+    let tags: Vec<String> = http::get_json_pages::<GitLabTag>(&tags_url, &headers)?
+        .ok_or_else(|| format!("GitLab project {project_path} not found"))?
+        .into_iter()
+        .map(|tag| tag.name)
+        .collect();
+
+    version::highest_stable_tag(&tags)
+        .ok_or_else(|| format!("No stable tags found for repository {project_path}").into())
+}
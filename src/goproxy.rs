@@ -0,0 +1,264 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::{
+    cup_parser::FileTarget,
+    http,
+    init::Config,
+    version,
+    version::Version,
+    version_source::{Release, VersionSource},
+};
+
+/// Proxy used when neither `cup.toml` nor `GOPROXY` names one
+const DEFAULT_GOPROXY: &str = "https://proxy.golang.org";
+
+// Timestamp and commit hash ending every pseudo-version, e.g. v0.0.0-20240101120000-abcdef123456
+static PSEUDO_VERSION_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"[-.]\d{14}-[0-9a-f]{12}(?:\+incompatible)?$")
+        .expect("Failed to compile pseudo-version regex")
+});
+
+// Major version suffix of a module path, e.g. /v2
+static MAJOR_SUFFIX_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"/v([2-9]|[1-9][0-9]+)$").expect("Failed to compile major regex"));
+
+// Major version suffix of a gopkg.in path, e.g. gopkg.in/yaml.v3
+static GOPKG_SUFFIX_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^gopkg\.in/.+\.v(0|[1-9][0-9]*)(?:-unstable)?$")
+        .expect("Failed to compile gopkg.in major regex")
+});
+
+/// Body of `@latest` and `@v/<version>.info`
+#[derive(Debug, Deserialize)]
+struct VersionInfo {
+    #[serde(rename = "Version")]
+    version: String,
+    #[serde(rename = "Time", default)]
+    time: Option<String>,
+}
+
+/// Escapes upper-case letters as `!` plus the lower-case letter, as the proxy protocol requires
+fn encode_module_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for c in path.chars() {
+        if c.is_ascii_uppercase() {
+            encoded.push('!');
+            encoded.push(c.to_ascii_lowercase());
+        } else {
+            encoded.push(c);
+        }
+    }
+    encoded
+}
+
+fn is_pseudo_version(version: &str) -> bool {
+    PSEUDO_VERSION_RE.is_match(version)
+}
+
+/// Checks that a version belongs to the module's major line: `/vN` and gopkg.in `.vN`
+/// paths only carry vN.x.y, paths without a suffix carry v0 and v1 (never `+incompatible`)
+fn matches_major(module: &str, version: &Version, raw: &str) -> bool {
+    let major = version.numbers.first().copied().unwrap_or(0);
+    let suffix = GOPKG_SUFFIX_RE
+        .captures(module)
+        .or_else(|| MAJOR_SUFFIX_RE.captures(module));
+    match suffix {
+        Some(caps) => caps[1].parse::<u64>().is_ok_and(|n| n == major),
+        None => major <= 1 && !raw.ends_with("+incompatible"),
+    }
+}
+
+/// Resolves the proxy base URL: `cup.toml` first, then the first usable `GOPROXY` entry
+fn proxy_base(config: &Config) -> String {
+    let from_env = std::env::var("GOPROXY").ok().and_then(|value| {
+        value
+            .split([',', '|'])
+            .map(str::trim)
+            .find(|entry| !entry.is_empty() && *entry != "direct" && *entry != "off")
+            .map(str::to_string)
+    });
+
+    config
+        .goproxy
+        .url
+        .clone()
+        .or(from_env)
+        .unwrap_or_else(|| DEFAULT_GOPROXY.to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// Fetches a proxy endpoint over HTTP or, for `file://` proxies, from the local directory
+fn fetch(base: &str, path: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    if let Some(dir) = base.strip_prefix("file://") {
+        let file = std::path::Path::new(dir).join(path);
+        return match std::fs::read_to_string(&file) {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Failed to read {}: {e}", file.display()).into()),
+        };
+    }
+
+    match http::get(&format!("{base}/{path}"), &[])? {
+        Some(response) => Ok(Some(response.into_string()?)),
+        None => Ok(None),
+    }
+}
+
+/// Versions of a Go module served by a module proxy
+pub struct GoProxySource;
+
+impl VersionSource for GoProxySource {
+    fn describe(&self, target: &FileTarget) -> String {
+        format!("Go module {}", target.extracted_config.tag.remote_tag)
+    }
+
+    /// Lists tagged releases of the module's major line, or its pseudo-version when it has none
+    fn list_versions(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let module = &target.extracted_config.tag.remote_tag;
+        let base = proxy_base(config);
+
+        let tagged = list_tagged_versions(module, &base)?;
+        if !tagged.is_empty() {
+            return Ok(tagged);
+        }
+
+        Ok(vec![
+            latest_untagged_version(module, &base, config)?.version,
+        ])
+    }
+
+    /// Lists the same versions with the `Time` of their `@v/<version>.info`
+    fn list_releases(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+        let module = &target.extracted_config.tag.remote_tag;
+        let base = proxy_base(config);
+
+        let tagged = list_tagged_versions(module, &base)?;
+        if tagged.is_empty() {
+            let info = latest_untagged_version(module, &base, config)?;
+            return Ok(vec![Release::new(info.version, info.time.as_deref())]);
+        }
+
+        Ok(tagged
+            .into_iter()
+            .map(|version| {
+                let time = version_info(module, &base, &version).and_then(|info| info.time);
+                Release::new(version, time.as_deref())
+            })
+            .collect())
+    }
+
+    fn latest(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        get_latest_tag_from_goproxy(target, config)
+    }
+}
+
+/// Lists the tagged versions of a module from `@v/list` that belong to its major line
+fn list_tagged_versions(
+    module: &str,
+    base: &str,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let list = fetch(base, &format!("{}/@v/list", encode_module_path(module)))?
+        .ok_or_else(|| format!("Module {module} not found at {base}"))?;
+
+    Ok(list
+        .lines()
+        .map(str::trim)
+        .filter(|raw| !raw.is_empty() && !is_pseudo_version(raw))
+        .filter(|raw| {
+            Version::parse(raw).is_some_and(|version| matches_major(module, &version, raw))
+        })
+        .map(str::to_string)
+        .collect())
+}
+
+/// Reads `@v/<version>.info`, `None` when the proxy does not serve it
+fn version_info(module: &str, base: &str, version: &str) -> Option<VersionInfo> {
+    let path = format!(
+        "{}/@v/{}.info",
+        encode_module_path(module),
+        encode_module_path(version)
+    );
+    let info = fetch(base, &path).ok()??;
+    serde_json::from_str(&info).ok()
+}
+
+/// Modules without tagged releases only have a pseudo-version through `@latest`
+fn latest_untagged_version(
+    module: &str,
+    base: &str,
+    config: &Config,
+) -> Result<VersionInfo, Box<dyn std::error::Error>> {
+    let info = fetch(base, &format!("{}/@latest", encode_module_path(module)))?
+        .ok_or_else(|| format!("No versions found for module {module}"))?;
+    let info: VersionInfo = serde_json::from_str(&info)?;
+
+    if is_pseudo_version(&info.version) && !config.goproxy.pseudo_versions {
+        return Err(format!(
+            "Module {module} only has pseudo-version {}, set pseudo_versions = true in [goproxy] to use it",
+            info.version
+        )
+        .into());
+    }
+
+    Ok(info)
+}
+
+pub fn get_latest_tag_from_goproxy(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let module = &target.extracted_config.tag.remote_tag;
+    let base = proxy_base(config);
+
+    // First pick the highest tagged release from the version list
+    let tagged = list_tagged_versions(module, &base)?;
+    if let Some(latest) = version::highest_stable(&tagged) {
+        return Ok(latest.to_string());
+    }
+
+    println!("release not found");
+    Ok(latest_untagged_version(module, &base, config)?.version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(module: &str, raw: &str) -> bool {
+        matches_major(module, &Version::parse(raw).unwrap(), raw)
+    }
+
+    #[test]
+    fn path_suffix_selects_major() {
+        assert!(matches("example.com/mod", "v1.2.0"));
+        assert!(matches("example.com/mod", "v0.3.0"));
+        assert!(!matches("example.com/mod", "v2.0.0+incompatible"));
+        assert!(matches("example.com/mod/v2", "v2.1.0"));
+        assert!(!matches("example.com/mod/v2", "v3.0.0"));
+    }
+
+    #[test]
+    fn gopkg_in_suffix_selects_major() {
+        assert!(matches("gopkg.in/yaml.v3", "v3.0.1"));
+        assert!(!matches("gopkg.in/yaml.v3", "v2.4.0"));
+        assert!(matches("gopkg.in/yaml.v2", "v2.4.0"));
+        assert!(matches("gopkg.in/check.v1", "v1.0.0"));
+        assert!(!matches("gopkg.in/check.v1", "v0.9.0"));
+        assert!(!matches("example.com/yaml.v3", "v3.0.0"));
+    }
+}
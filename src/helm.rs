@@ -0,0 +1,249 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::{
+    cup_parser::FileTarget,
+    http,
+    init::Config,
+    oci::{ImageReference, RegistryClient, select_tag},
+    version::Version,
+    version_source::{Release, VersionSource},
+};
+
+/// Trailing annotation word selecting the chart's `appVersion` instead of its `version`
+pub const APP_VERSION_FIELD: &str = "appVersion";
+
+#[derive(Debug, Deserialize)]
+struct RepositoryIndex {
+    #[serde(default)]
+    entries: HashMap<String, Vec<ChartEntry>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChartEntry {
+    version: String,
+    #[serde(rename = "appVersion")]
+    app_version: Option<String>,
+    #[serde(default)]
+    deprecated: bool,
+    /// When the chart version was packaged into the index
+    #[serde(default)]
+    created: Option<String>,
+}
+
+/// A chart location such as `https://charts.example.com ingress-nginx appVersion`
+struct ChartReference<'a> {
+    repository: &'a str,
+    chart: Option<&'a str>,
+    app_version: bool,
+}
+
+impl<'a> ChartReference<'a> {
+    fn parse(location: &'a str) -> ChartReference<'a> {
+        let mut parts = location.split_whitespace();
+        let repository = parts.next().unwrap_or_default();
+        let mut chart = parts.next();
+        let mut app_version = parts.next() == Some(APP_VERSION_FIELD);
+
+        // OCI charts carry the chart name in the reference itself
+        if repository.starts_with("oci://") && chart == Some(APP_VERSION_FIELD) {
+            chart = None;
+            app_version = true;
+        }
+
+        ChartReference {
+            repository,
+            chart,
+            app_version,
+        }
+    }
+}
+
+/// Lists the non-deprecated entries of a chart from the repository's `index.yaml`
+fn repository_entries(
+    reference: &ChartReference,
+) -> Result<Vec<ChartEntry>, Box<dyn std::error::Error>> {
+    let repository = reference.repository.trim_end_matches('/');
+    let chart = reference
+        .chart
+        .ok_or_else(|| format!("No chart name given for repository {repository}"))?;
+
+    let url = format!("{repository}/index.yaml");
+    let raw = http::get(&url, &[])?
+        .ok_or_else(|| format!("Chart repository index {url} not found"))?
+        .into_string()?;
+    let mut index: RepositoryIndex = serde_yaml::from_str(&raw)?;
+
+    let entries = index
+        .entries
+        .remove(chart)
+        .ok_or_else(|| format!("Chart {chart} not found in {repository}"))?;
+
+    Ok(entries
+        .into_iter()
+        .filter(|entry| !entry.deprecated)
+        .collect())
+}
+
+/// Lists chart versions from an OCI registry, restoring the `+` Helm stores as `_` in tags
+fn oci_versions(
+    reference: &ChartReference,
+) -> Result<(String, Vec<String>), Box<dyn std::error::Error>> {
+    let location = reference.repository.trim_start_matches("oci://");
+    let location = match reference.chart {
+        Some(chart) => format!("{}/{chart}", location.trim_end_matches('/')),
+        None => location.to_string(),
+    };
+    let image = ImageReference::parse(&location)
+        .ok_or_else(|| format!("Invalid chart reference {location}"))?;
+
+    let versions = RegistryClient::new(&image)
+        .list_tags()?
+        .into_iter()
+        .map(|tag| tag.replace('_', "+"))
+        .collect();
+
+    Ok((location, versions))
+}
+
+fn latest_from_repository(
+    reference: &ChartReference,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let chart = reference.chart.unwrap_or_default();
+    let entries = repository_entries(reference)?;
+
+    let latest = entries
+        .iter()
+        .filter_map(|entry| Version::parse(&entry.version).map(|version| (version, entry)))
+        .filter(|(version, _)| version.is_stable())
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, entry)| entry)
+        .ok_or_else(|| format!("No stable versions found for chart {chart}"))?;
+
+    if reference.app_version {
+        latest
+            .app_version
+            .clone()
+            .ok_or_else(|| format!("Chart {chart} {} has no appVersion", latest.version).into())
+    } else {
+        Ok(latest.version.clone())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ChartMetadata {
+    #[serde(rename = "appVersion")]
+    app_version: Option<String>,
+}
+
+fn latest_from_oci(reference: &ChartReference) -> Result<String, Box<dyn std::error::Error>> {
+    let (location, versions) = oci_versions(reference)?;
+    let version = select_tag(&versions, None)
+        .ok_or_else(|| format!("No version tags found for chart {location}"))?;
+
+    if reference.app_version {
+        let image = ImageReference::parse(&location)
+            .ok_or_else(|| format!("Invalid chart reference {location}"))?;
+        let metadata: ChartMetadata =
+            RegistryClient::new(&image).config_blob(&version.replace('+', "_"))?;
+        metadata
+            .app_version
+            .ok_or_else(|| format!("Chart {location} {version} has no appVersion").into())
+    } else {
+        Ok(version)
+    }
+}
+
+/// Versions of a chart in a Helm repository or OCI registry
+pub struct HelmSource;
+
+impl VersionSource for HelmSource {
+    fn describe(&self, target: &FileTarget) -> String {
+        format!("Helm chart {}", target.extracted_config.tag.remote_tag)
+    }
+
+    fn list_versions(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        Ok(self
+            .list_releases(target, config)?
+            .into_iter()
+            .map(|release| release.tag)
+            .collect())
+    }
+
+    /// Lists chart versions, or the `appVersion`s they ship when the annotation asks for them,
+    /// with the time `index.yaml` says they were created; registries record no such time
+    fn list_releases(
+        &self,
+        target: &FileTarget,
+        _config: &Config,
+    ) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+        let reference = ChartReference::parse(&target.extracted_config.tag.remote_tag);
+
+        // Reading every appVersion from a registry means a manifest per tag, take the latest only
+        if reference.repository.starts_with("oci://") {
+            let versions = if reference.app_version {
+                vec![latest_from_oci(&reference)?]
+            } else {
+                oci_versions(&reference)?.1
+            };
+            return Ok(versions
+                .into_iter()
+                .map(|version| Release::new(version, None))
+                .collect());
+        }
+
+        // Several chart versions can ship the same appVersion, keep its earliest publish time
+        let mut releases: Vec<Release> = Vec::new();
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        for entry in repository_entries(&reference)? {
+            let version = if reference.app_version {
+                let Some(app_version) = entry.app_version else {
+                    continue;
+                };
+                app_version
+            } else {
+                entry.version
+            };
+            let release = Release::new(version, entry.created.as_deref());
+
+            match seen.get(&release.tag) {
+                Some(&index) => {
+                    let kept = &mut releases[index];
+                    if release
+                        .published
+                        .is_some_and(|published| kept.published.is_none_or(|kept| published < kept))
+                    {
+                        kept.published = release.published;
+                    }
+                }
+                None => {
+                    seen.insert(release.tag.clone(), releases.len());
+                    releases.push(release);
+                }
+            }
+        }
+        Ok(releases)
+    }
+
+    fn latest(
+        &self,
+        target: &FileTarget,
+        _config: &Config,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        get_latest_tag_from_helm(target)
+    }
+}
+
+pub fn get_latest_tag_from_helm(target: &FileTarget) -> Result<String, Box<dyn std::error::Error>> {
+    let reference = ChartReference::parse(&target.extracted_config.tag.remote_tag);
+
+    if reference.repository.starts_with("oci://") {
+        latest_from_oci(&reference)
+    } else {
+        latest_from_repository(&reference)
+    }
+}
@@ -0,0 +1,165 @@
+use base64::Engine;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+
+/// Shared HTTP agent so connections are pooled across lookups
+static AGENT: Lazy<ureq::Agent> = Lazy::new(|| {
+    ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(30))
+        .user_agent(concat!("cup/", env!("CARGO_PKG_VERSION")))
+        .build()
+});
+
+/// Most pages a paginated listing follows, e.g. 1000 tags at 100 per page
+pub const MAX_PAGES: usize = 10;
+
+// <url>; rel="next" entry of a Link header
+static NEXT_LINK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"<([^>]+)>\s*;\s*rel="?next"?"#).expect("Failed to compile link regex")
+});
+
+// ${VAR} references inside configured header and token values
+static ENV_REF_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\$\{(\w+)\}").expect("Failed to compile env reference regex"));
+
+/// Performs a GET request without any status handling
+///
+/// Use this when a provider needs to inspect error responses itself,
+/// e.g. to answer an authentication challenge
+pub fn call(url: &str, headers: &[(&str, String)]) -> Result<ureq::Response, Box<ureq::Error>> {
+    let mut request = AGENT.get(url);
+    for (name, value) in headers {
+        request = request.set(name, value);
+    }
+    request.call().map_err(Box::new)
+}
+
+/// Performs a GET request and returns the raw response
+///
+/// # Arguments
+/// * `url` - The full URL to request
+/// * `headers` - Additional request headers as name/value pairs
+///
+/// # Returns
+/// * `Ok(Some(Response))` when the server answered with a success status
+/// * `Ok(None)` when the server answered with 404 Not Found
+/// * `Err` for any other status or transport error
+pub fn get(
+    url: &str,
+    headers: &[(&str, String)],
+) -> Result<Option<ureq::Response>, Box<dyn std::error::Error>> {
+    match call(url, headers).map_err(|e| *e) {
+        Ok(response) => Ok(Some(response)),
+        Err(ureq::Error::Status(404, _)) => Ok(None),
+        Err(ureq::Error::Status(code, response)) => Err(status_error(url, code, response).into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Reads the `rel="next"` URL of a response's `Link` header, as used for pagination
+///
+/// Falls back to GitLab's `X-Next-Page` header, which replaces the `page` query parameter
+pub fn next_link(response: &ureq::Response) -> Option<String> {
+    if let Some(caps) = response
+        .header("link")
+        .and_then(|link| NEXT_LINK_RE.captures(link))
+    {
+        return Some(caps[1].to_string());
+    }
+
+    let page = response
+        .header("x-next-page")
+        .filter(|page| !page.is_empty())?;
+    let url = response.get_url();
+    let (base, query) = url.split_once('?').unwrap_or((url, ""));
+    let params = query
+        .split('&')
+        .filter(|param| !param.is_empty() && !param.starts_with("page="))
+        .chain(std::iter::once(format!("page={page}").as_str()))
+        .collect::<Vec<_>>()
+        .join("&");
+    Some(format!("{base}?{params}"))
+}
+
+/// Performs GET requests for a JSON array, following `Link: rel="next"` for at most
+/// `MAX_PAGES` pages
+///
+/// Behaves like [`get_json`], with `Ok(None)` meaning the first page was 404 Not Found
+pub fn get_json_pages<T: DeserializeOwned>(
+    url: &str,
+    headers: &[(&str, String)],
+) -> Result<Option<Vec<T>>, Box<dyn std::error::Error>> {
+    let Some(mut response) = get(url, headers)? else {
+        return Ok(None);
+    };
+
+    let mut items = Vec::new();
+    for _ in 1..MAX_PAGES {
+        let next = next_link(&response);
+        items.extend(response.into_json::<Vec<T>>()?);
+        match next.map(|next| get(&next, headers)).transpose()?.flatten() {
+            Some(next) => response = next,
+            None => return Ok(Some(items)),
+        }
+    }
+    items.extend(response.into_json::<Vec<T>>()?);
+
+    Ok(Some(items))
+}
+
+/// Performs a GET request and deserializes the JSON response body
+///
+/// Behaves like [`get`], with `Ok(None)` meaning 404 Not Found
+pub fn get_json<T: DeserializeOwned>(
+    url: &str,
+    headers: &[(&str, String)],
+) -> Result<Option<T>, Box<dyn std::error::Error>> {
+    match get(url, headers)? {
+        Some(response) => Ok(Some(response.into_json::<T>()?)),
+        None => Ok(None),
+    }
+}
+
+/// Expands `${VAR}` references from the environment, unset variables expand to nothing
+pub fn expand_env(value: &str) -> String {
+    ENV_REF_RE
+        .replace_all(value, |caps: &regex::Captures| {
+            std::env::var(&caps[1]).unwrap_or_default()
+        })
+        .to_string()
+}
+
+/// Builds a `Basic` `Authorization` value from a username and password in environment
+/// variables, `None` when the username is unset; a missing password counts as empty
+pub fn basic_credentials(user_var: &str, pass_var: &str) -> Option<String> {
+    let username = std::env::var(user_var).ok()?;
+    let password = std::env::var(pass_var).unwrap_or_default();
+    let encoded =
+        base64::engine::general_purpose::STANDARD.encode(format!("{username}:{password}"));
+    Some(format!("Basic {encoded}"))
+}
+
+/// Builds a readable error for a non-success status, calling out rate limits
+pub fn status_error(url: &str, code: u16, response: ureq::Response) -> String {
+    let remaining = response
+        .header("x-ratelimit-remaining")
+        .or_else(|| response.header("ratelimit-remaining"))
+        .map(str::to_string);
+    let reset = response
+        .header("x-ratelimit-reset")
+        .or_else(|| response.header("ratelimit-reset"))
+        .or_else(|| response.header("retry-after"))
+        .map(str::to_string);
+    let body = response.into_string().unwrap_or_default();
+
+    if code == 429 || (code == 403 && remaining.as_deref() == Some("0")) {
+        match reset {
+            Some(reset) => format!("{url} is rate limited (HTTP {code}), resets at {reset}"),
+            None => format!("{url} is rate limited (HTTP {code})"),
+        }
+    } else {
+        format!("{url} returned HTTP {code}: {}", body.trim())
+    }
+}
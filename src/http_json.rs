@@ -0,0 +1,169 @@
+use serde_json::Value;
+
+use crate::{cup_parser::FileTarget, http, init::Config, version, version_source::VersionSource};
+
+/// One step of a selector such as `$.releases[*].version`
+#[derive(Debug, PartialEq)]
+enum Step {
+    Key(String),
+    Index(i64),
+    Wildcard,
+}
+
+/// Parses a JSONPath (`$.a[*].b`, `$['a'][0]`) or jq-style (`.a[].b`) selector
+fn parse_selector(selector: &str) -> Result<Vec<Step>, String> {
+    let selector = selector.trim();
+    let mut rest = selector.strip_prefix('$').unwrap_or(selector);
+    let mut steps = Vec::new();
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let end = after
+                .find(']')
+                .ok_or_else(|| format!("Unclosed '[' in selector {selector}"))?;
+            let inner = after[..end].trim();
+            let step = match inner {
+                "" | "*" => Step::Wildcard,
+                quoted
+                    if quoted.len() >= 2
+                        && (quoted.starts_with('\'') || quoted.starts_with('"')) =>
+                {
+                    Step::Key(quoted[1..quoted.len() - 1].to_string())
+                }
+                index => Step::Index(
+                    index
+                        .parse()
+                        .map_err(|_| format!("Invalid index [{index}] in selector {selector}"))?,
+                ),
+            };
+            steps.push(step);
+            rest = &after[end + 1..];
+        } else if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            match &after[..end] {
+                "" => {}
+                "*" => steps.push(Step::Wildcard),
+                key => steps.push(Step::Key(key.to_string())),
+            }
+            rest = &after[end..];
+        } else {
+            return Err(format!("Unexpected '{rest}' in selector {selector}"));
+        }
+    }
+
+    Ok(steps)
+}
+
+/// Applies selector steps to a document, collecting every matched value
+fn select<'a>(document: &'a Value, steps: &[Step]) -> Vec<&'a Value> {
+    let mut current = vec![document];
+
+    for step in steps {
+        current = current
+            .into_iter()
+            .flat_map(|value| -> Vec<&Value> {
+                match (step, value) {
+                    (Step::Key(key), Value::Object(map)) => map.get(key).into_iter().collect(),
+                    (Step::Index(index), Value::Array(items)) => {
+                        let position = if *index < 0 {
+                            items.len().checked_sub(index.unsigned_abs() as usize)
+                        } else {
+                            Some(*index as usize)
+                        };
+                        position.and_then(|i| items.get(i)).into_iter().collect()
+                    }
+                    (Step::Wildcard, Value::Array(items)) => items.iter().collect(),
+                    (Step::Wildcard, Value::Object(map)) => map.values().collect(),
+                    _ => vec![],
+                }
+            })
+            .collect();
+    }
+
+    current
+}
+
+/// Values selected from a JSON document served over HTTP
+pub struct HttpJsonSource;
+
+impl VersionSource for HttpJsonSource {
+    fn describe(&self, target: &FileTarget) -> String {
+        format!("HTTP source {}", target.extracted_config.tag.remote_tag)
+    }
+
+    fn list_versions(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        select_candidates(target, config)
+    }
+
+    fn latest(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        get_latest_tag_from_http(target, config)
+    }
+}
+
+/// Fetches the document and collects the string or number values the selector matches
+fn select_candidates(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let location = &target.extracted_config.tag.remote_tag;
+    let mut parts = location.split_whitespace();
+    let source = parts.next().unwrap_or_default();
+    let inline_selector = parts.next();
+
+    // Either a named [http.<name>] source from cup.toml or an inline URL
+    let (url, selector, headers) = match config.http.get(source) {
+        Some(named) => (
+            named.url.clone(),
+            inline_selector.unwrap_or(&named.selector).to_string(),
+            named
+                .headers
+                .iter()
+                .map(|(name, value)| (name.as_str(), http::expand_env(value)))
+                .collect::<Vec<_>>(),
+        ),
+        None if source.starts_with("http://") || source.starts_with("https://") => (
+            source.to_string(),
+            inline_selector.unwrap_or("$").to_string(),
+            vec![],
+        ),
+        None => return Err(format!("No [http.{source}] source configured in cup.toml").into()),
+    };
+
+    let steps = parse_selector(&selector)?;
+    let document =
+        http::get_json::<Value>(&url, &headers)?.ok_or_else(|| format!("{url} not found"))?;
+
+    let candidates: Vec<String> = select(&document, &steps)
+        .into_iter()
+        .filter_map(|value| match value {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        })
+        .collect();
+
+    if candidates.is_empty() {
+        return Err(format!("Selector {selector} matched no version in {url}").into());
+    }
+
+    Ok(candidates)
+}
+
+pub fn get_latest_tag_from_http(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let location = &target.extracted_config.tag.remote_tag;
+    let candidates = select_candidates(target, config)?;
+
+    version::single_or_highest(&candidates)
+        .ok_or_else(|| format!("No version among the values selected for {location}").into())
+}
@@ -1,6 +1,17 @@
+use globset::{GlobBuilder, GlobMatcher};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
+use std::fmt;
 use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::constraint::Constraint;
+
+/// Sparse index of the public crates.io registry
+pub const CRATES_IO_INDEX: &str = "https://index.crates.io";
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Config {
@@ -8,6 +19,175 @@ pub struct Config {
     pub cup_pattern: String,
     /// default remote type when not specified (e.g., "GitHub")
     pub remote_default: String,
+    /// settings for the GitHub remote
+    #[serde(default)]
+    pub github: GitHubConfig,
+    /// settings for the GitLab remote
+    #[serde(default)]
+    pub gitlab: GitLabConfig,
+    /// settings for the Crates remote
+    #[serde(default)]
+    pub crates: CratesConfig,
+    /// settings for the Npm remote
+    #[serde(default)]
+    pub npm: NpmConfig,
+    /// settings for the PyPI remote
+    #[serde(default)]
+    pub pypi: PyPIConfig,
+    /// settings for the Maven remote
+    #[serde(default)]
+    pub maven: MavenConfig,
+    /// settings for the GoProxy remote
+    #[serde(default)]
+    pub goproxy: GoProxyConfig,
+    /// settings for the Terraform remote
+    #[serde(default)]
+    pub terraform: TerraformConfig,
+    /// settings for the RubyGems remote
+    #[serde(default)]
+    pub rubygems: RubyGemsConfig,
+    /// settings for the NuGet remote
+    #[serde(default)]
+    pub nuget: NuGetConfig,
+    /// settings for the Packagist remote
+    #[serde(default)]
+    pub packagist: PackagistConfig,
+    /// named JSON endpoints for the Http remote, referenced as `[cup] Http <name>`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub http: HashMap<String, HttpSource>,
+    /// named programs for the Command remote, referenced as `[cup] Command <name> <arg>`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub command: HashMap<String, CommandSource>,
+    /// external `cup-provider-<name>` programs, referenced as `[cup] <name> <args>`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub plugin: HashMap<String, PluginSource>,
+    /// per-path and per-source settings as `[[rule]]` tables, later matching rules override earlier ones
+    #[serde(default, rename = "rule", skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<Rule>,
+    /// time a version must have been published before it is adopted (e.g., "3d")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_age: Option<MinAge>,
+    /// known-bad versions per source as `[[ignore]]` tables
+    #[serde(default, rename = "ignore", skip_serializing_if = "Vec::is_empty")]
+    pub ignores: Vec<Ignore>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GitHubConfig {
+    /// base URL of the REST API (e.g., "https://github.example.com/api/v3" for Enterprise Server)
+    pub url: String,
+    /// retry through the `gh` CLI when the REST API lookup fails
+    #[serde(default)]
+    pub gh_fallback: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GitLabConfig {
+    /// base URL of the GitLab instance (e.g., "https://gitlab.example.com")
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CratesConfig {
+    /// sparse index URL of the registry (e.g., "sparse+https://cargo.example.com/index/")
+    pub index: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NpmConfig {
+    /// registry URL (e.g., "https://npm.example.com" for a private registry)
+    pub registry: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PyPIConfig {
+    /// base URL of the JSON API, queried at "<url>/pypi/<name>/json"
+    pub url: String,
+    /// PEP 691 simple index used instead of the JSON API (e.g., "http://localhost:3141/root/pypi/+simple")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub simple_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MavenConfig {
+    /// repository URL (e.g., "https://maven.example.com/releases")
+    pub url: String,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct GoProxyConfig {
+    /// module proxy URL, `file://` for a local directory; falls back to `GOPROXY`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// accept a pseudo-version when a module has no tagged release
+    #[serde(default)]
+    pub pseudo_versions: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TerraformConfig {
+    /// registry used for addresses without a host (e.g., "https://tf.example.com")
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RubyGemsConfig {
+    /// gem server URL (e.g., "https://gems.example.com")
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NuGetConfig {
+    /// V3 service index URL (e.g., "https://nuget.example.com/v3/index.json")
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PackagistConfig {
+    /// Composer repository URL (e.g., "https://packages.example.com")
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HttpSource {
+    /// JSON endpoint to query
+    pub url: String,
+    /// JSONPath or jq-style selector for the version(s) (e.g., "$.releases[*].version")
+    #[serde(default = "default_selector")]
+    pub selector: String,
+    /// extra request headers, `${VAR}` is expanded from the environment
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+fn default_selector() -> String {
+    "$".to_string()
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CommandSource {
+    /// program and arguments, "{arg}" is replaced by the annotation's argument
+    pub command: Vec<String>,
+    /// seconds to wait before the program is killed
+    #[serde(default = "default_command_timeout")]
+    pub timeout_secs: u64,
+}
+
+fn default_command_timeout() -> u64 {
+    60
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PluginSource {
+    /// executable to run, defaults to "cup-provider-<name>" (lower-cased) looked up on PATH
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// options sent with every request, annotation `key=value` arguments override them
+    #[serde(default)]
+    pub options: serde_json::Map<String, serde_json::Value>,
+    /// seconds to wait before the plugin is killed
+    #[serde(default = "default_command_timeout")]
+    pub timeout_secs: u64,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -18,14 +198,220 @@ pub struct Target {
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Tag {
-    /// The repository location where releases can be found (e.g., "owner/repo" for GitHub)
+    /// The repository location where releases can be found (e.g., "owner/repo" for GitHub, a clone URL for Git)
     pub remote_tag: String,
     pub remote_type: Remote,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub enum Remote {
     GitHub,
+    GitLab,
+    Git,
+    Oci,
+    Crates,
+    Npm,
+    PyPI,
+    Maven,
+    GoProxy,
+    Helm,
+    Terraform,
+    RubyGems,
+    NuGet,
+    Packagist,
+    Http,
+    Command,
+    File,
+    /// An external plugin registered under `[plugin.<name>]`
+    Plugin(String),
+}
+
+impl Remote {
+    /// Looks up a remote by the name annotations and `remote_default` use, e.g. `GitHub` or the
+    /// name of a `[plugin.<name>]`
+    pub fn from_name(name: &str, config: &Config) -> Option<Remote> {
+        let remote = match name {
+            "GitHub" => Remote::GitHub,
+            "GitLab" => Remote::GitLab,
+            "Git" => Remote::Git,
+            "Oci" => Remote::Oci,
+            "Crates" => Remote::Crates,
+            "Npm" => Remote::Npm,
+            "PyPI" => Remote::PyPI,
+            "Maven" => Remote::Maven,
+            "GoProxy" => Remote::GoProxy,
+            "Helm" => Remote::Helm,
+            "Terraform" => Remote::Terraform,
+            "RubyGems" => Remote::RubyGems,
+            "NuGet" => Remote::NuGet,
+            "Packagist" => Remote::Packagist,
+            "Http" => Remote::Http,
+            "Command" => Remote::Command,
+            "File" => Remote::File,
+            plugin if config.plugin.contains_key(plugin) => Remote::Plugin(plugin.to_string()),
+            _ => return None,
+        };
+        Some(remote)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Rule {
+    /// glob matched against file paths relative to the repository root (e.g., "infra/**/*.tf")
+    #[serde(default = "default_rule_path")]
+    pub path: String,
+    /// annotation location the rule is limited to (e.g., "google/gson")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// largest kind of update applied automatically to matching files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update: Option<UpdateLevel>,
+    /// regex selecting one family of tags, capturing the version (e.g., "gson-parent-(.*)")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// how picked versions are written (e.g., "v{major}.{minor}.{patch}", "same")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// time a version must have been published before it is adopted in matching files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_age: Option<MinAge>,
+    /// `path` compiled once the configuration is loaded
+    #[serde(skip)]
+    matcher: Option<GlobMatcher>,
+}
+
+/// Versions of a source that are never picked, e.g. a broken release
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Ignore {
+    /// annotation location the versions belong to (e.g., "square/okhttp")
+    pub source: String,
+    /// exact versions or ranges in constraint syntax (e.g., "5.3.0", ">=5.3.0, <5.3.2")
+    pub versions: Vec<String>,
+    /// why the versions are ignored, shown when one is passed over
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+fn default_rule_path() -> String {
+    "**".to_string()
+}
+
+impl Rule {
+    /// Compiles `path`, where `*` and `?` stay within one directory and only `**` crosses `/`
+    fn compile(&mut self) -> Result<(), String> {
+        let glob = GlobBuilder::new(&self.path)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| format!("Invalid path glob in [[rule]]: {e}"))?;
+        self.matcher = Some(glob.compile_matcher());
+        Ok(())
+    }
+
+    /// Checks whether the rule applies to an annotation location in a file such as `./infra/main.tf`
+    pub fn matches(&self, path: &Path, source: &str) -> bool {
+        let path = path.strip_prefix(".").unwrap_or(path);
+        self.source.as_deref().is_none_or(|s| s == source)
+            && self
+                .matcher
+                .as_ref()
+                .is_some_and(|matcher| matcher.is_match(path))
+    }
+}
+
+/// How far an update may move from the current version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateLevel {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl fmt::Display for UpdateLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            UpdateLevel::Patch => "patch",
+            UpdateLevel::Minor => "minor",
+            UpdateLevel::Major => "major",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for UpdateLevel {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "patch" => Ok(UpdateLevel::Patch),
+            "minor" => Ok(UpdateLevel::Minor),
+            "major" => Ok(UpdateLevel::Major),
+            other => Err(format!(
+                "Unknown update level {other}, expected patch, minor or major"
+            )),
+        }
+    }
+}
+
+/// Cooldown before adopting a release, written as a number and a unit: `90m`, `12h`, `3d`, `2w`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct MinAge(pub Duration);
+
+const AGE_UNITS: [(&str, u64); 5] = [
+    ("w", 7 * 24 * 60 * 60),
+    ("d", 24 * 60 * 60),
+    ("h", 60 * 60),
+    ("m", 60),
+    ("s", 1),
+];
+
+impl fmt::Display for MinAge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.0.as_secs();
+        let (unit, size) = AGE_UNITS
+            .iter()
+            .find(|(_, size)| secs.is_multiple_of(*size))
+            .unwrap_or(&("s", 1));
+        write!(f, "{}{unit}", secs / size)
+    }
+}
+
+impl FromStr for MinAge {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let split = value
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(value.len());
+        let (number, unit) = value.split_at(split);
+
+        let number: u64 = number
+            .parse()
+            .map_err(|_| format!("Invalid min_age {value}, expected e.g. 12h, 3d or 2w"))?;
+        let size = AGE_UNITS
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .map(|(_, size)| *size)
+            .ok_or_else(|| format!("Unknown unit in min_age {value}, expected s, m, h, d or w"))?;
+
+        Ok(MinAge(Duration::from_secs(number * size)))
+    }
+}
+
+impl TryFrom<String> for MinAge {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<MinAge> for String {
+    fn from(age: MinAge) -> Self {
+        age.to_string()
+    }
 }
 
 impl Default for Config {
@@ -33,11 +419,161 @@ impl Default for Config {
         Config {
             cup_pattern: "cup".to_string(),
             remote_default: "GitHub".to_string(),
+            github: GitHubConfig::default(),
+            gitlab: GitLabConfig::default(),
+            crates: CratesConfig::default(),
+            npm: NpmConfig::default(),
+            pypi: PyPIConfig::default(),
+            maven: MavenConfig::default(),
+            goproxy: GoProxyConfig::default(),
+            terraform: TerraformConfig::default(),
+            rubygems: RubyGemsConfig::default(),
+            nuget: NuGetConfig::default(),
+            packagist: PackagistConfig::default(),
+            http: HashMap::new(),
+            command: HashMap::new(),
+            plugin: HashMap::new(),
+            rules: Vec::new(),
+            min_age: None,
+            ignores: Vec::new(),
+        }
+    }
+}
+
+impl Default for GitHubConfig {
+    fn default() -> Self {
+        GitHubConfig {
+            url: "https://api.github.com".to_string(),
+            gh_fallback: false,
+        }
+    }
+}
+
+impl GitHubConfig {
+    /// Base URL without a trailing slash, ready to have API paths appended
+    pub fn api_base(&self) -> &str {
+        self.url.trim_end_matches('/')
+    }
+}
+
+impl Default for GitLabConfig {
+    fn default() -> Self {
+        GitLabConfig {
+            url: "https://gitlab.com".to_string(),
+        }
+    }
+}
+
+impl GitLabConfig {
+    /// Base URL without a trailing slash, ready to have API paths appended
+    pub fn api_base(&self) -> &str {
+        self.url.trim_end_matches('/')
+    }
+}
+
+impl Default for CratesConfig {
+    fn default() -> Self {
+        CratesConfig {
+            index: CRATES_IO_INDEX.to_string(),
+        }
+    }
+}
+
+impl CratesConfig {
+    /// Index URL without the `sparse+` marker or a trailing slash
+    pub fn index_base(&self) -> &str {
+        let index = self.index.strip_prefix("sparse+").unwrap_or(&self.index);
+        index.trim_end_matches('/')
+    }
+
+    pub fn is_crates_io(&self) -> bool {
+        self.index_base() == CRATES_IO_INDEX
+    }
+}
+
+impl Default for NpmConfig {
+    fn default() -> Self {
+        NpmConfig {
+            registry: "https://registry.npmjs.org".to_string(),
+        }
+    }
+}
+
+impl NpmConfig {
+    /// Registry URL without a trailing slash, ready to have package names appended
+    pub fn registry_base(&self) -> &str {
+        self.registry.trim_end_matches('/')
+    }
+}
+
+impl Default for PyPIConfig {
+    fn default() -> Self {
+        PyPIConfig {
+            url: "https://pypi.org".to_string(),
+            simple_url: None,
+        }
+    }
+}
+
+impl Default for MavenConfig {
+    fn default() -> Self {
+        MavenConfig {
+            url: "https://repo1.maven.org/maven2".to_string(),
+        }
+    }
+}
+
+impl Default for TerraformConfig {
+    fn default() -> Self {
+        TerraformConfig {
+            url: "https://registry.terraform.io".to_string(),
+        }
+    }
+}
+
+impl Default for RubyGemsConfig {
+    fn default() -> Self {
+        RubyGemsConfig {
+            url: "https://rubygems.org".to_string(),
+        }
+    }
+}
+
+impl Default for NuGetConfig {
+    fn default() -> Self {
+        NuGetConfig {
+            url: "https://api.nuget.org/v3/index.json".to_string(),
+        }
+    }
+}
+
+impl Default for PackagistConfig {
+    fn default() -> Self {
+        PackagistConfig {
+            url: "https://repo.packagist.org".to_string(),
         }
     }
 }
 
 impl Config {
+    /// Rules applying to an annotation location in a file, in the order they are written
+    pub fn rules_for<'a>(
+        &'a self,
+        path: &'a Path,
+        source: &'a str,
+    ) -> impl Iterator<Item = &'a Rule> + 'a {
+        self.rules
+            .iter()
+            .filter(move |rule| rule.matches(path, source))
+    }
+
+    /// `[[ignore]]` tables for an annotation location
+    pub fn ignores_for<'a>(&'a self, source: &'a str) -> impl Iterator<Item = &'a Ignore> + 'a {
+        self.ignores
+            .iter()
+            .filter(move |ignore| ignore.source == source)
+    }
+
     /// Load configuration from TOML file
     pub fn create() -> Result<(), String> {
         let current_dir = env::current_dir().map_err(|e| e.to_string())?;
@@ -63,7 +599,21 @@ impl Config {
             Ok(Config::default())
         } else {
             let raw = fs::read_to_string(file_path).map_err(|e| e.to_string())?;
-            let c = toml::from_str(&raw).map_err(|e| e.to_string())?;
+            let mut c: Config = toml::from_str(&raw).map_err(|e| e.to_string())?;
+            if Remote::from_name(&c.remote_default, &c).is_none() {
+                return Err(format!(
+                    "Unknown remote_default {}, expected a remote such as GitHub or a [plugin.<name>]",
+                    c.remote_default
+                ));
+            }
+            for rule in &mut c.rules {
+                rule.compile()?;
+            }
+            for ignore in &c.ignores {
+                for versions in &ignore.versions {
+                    Constraint::parse(versions).map_err(|e| format!("Invalid [[ignore]]: {e}"))?;
+                }
+            }
             Ok(c)
         }
     }
@@ -77,3 +627,31 @@ pub fn init() -> Result<(), String> {
 pub fn load_config() -> Result<Config, String> {
     Config::load()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compiled_rule(path: &str) -> Result<Rule, String> {
+        let mut rule: Rule = toml::from_str(&format!("path = {path:?}")).unwrap();
+        rule.compile()?;
+        Ok(rule)
+    }
+
+    #[test]
+    fn rule_star_stays_within_a_directory() {
+        let flat = compiled_rule("infra/*.tf").unwrap();
+        assert!(flat.matches(Path::new("./infra/main.tf"), "hashicorp/aws"));
+        assert!(!flat.matches(Path::new("./infra/prod/main.tf"), "hashicorp/aws"));
+
+        let recursive = compiled_rule("infra/**/*.tf").unwrap();
+        assert!(recursive.matches(Path::new("./infra/main.tf"), "hashicorp/aws"));
+        assert!(recursive.matches(Path::new("./infra/prod/main.tf"), "hashicorp/aws"));
+        assert!(!recursive.matches(Path::new("./app/main.tf"), "hashicorp/aws"));
+    }
+
+    #[test]
+    fn invalid_rule_glob_is_rejected() {
+        assert!(compiled_rule("infra/[").is_err());
+    }
+}
@@ -1,10 +1,35 @@
 use clap::{Parser, Subcommand};
 
+mod command;
+mod constraint;
+mod crates;
 mod cup_parser;
 mod file_finder;
+mod file_source;
+mod git;
+mod github;
+mod gitlab;
+mod goproxy;
+mod helm;
+mod http;
+mod http_json;
 mod init;
+mod maven;
+mod npm;
+mod nuget;
+mod oci;
+mod packagist;
+mod plugin;
+mod pypi;
+mod resolver;
+mod rubygems;
+mod tag_family;
+mod template;
+mod terraform;
 mod update;
+mod version;
 mod version_extractor;
+mod version_source;
 
 #[derive(Debug, Parser)]
 #[command(name = "cup")]
@@ -17,15 +42,20 @@ struct Cli {
 #[derive(Debug, Subcommand)]
 enum Commands {
     Init {},
-    Update {},
+    Update {
+        /// Apply versions older than the ones currently in files
+        #[arg(long)]
+        allow_downgrade: bool,
+    },
 }
 
 fn main() {
     let args = Cli::parse();
 
     match args.command {
-        Some(Commands::Update {}) => {
-            update::update(init::load_config().unwrap());
+        Some(Commands::Update { allow_downgrade }) => {
+            let options = update::UpdateOptions { allow_downgrade };
+            update::update(init::load_config().unwrap(), options);
         }
         Some(Commands::Init {}) => {
             if let Err(e) = init::init() {
@@ -34,7 +64,10 @@ fn main() {
             }
         }
         None => {
-            update::update(init::load_config().unwrap());
+            update::update(
+                init::load_config().unwrap(),
+                update::UpdateOptions::default(),
+            );
         }
     }
 }
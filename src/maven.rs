@@ -0,0 +1,389 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::cmp::Ordering;
+
+use crate::{
+    cup_parser::FileTarget, http, init::Config, version::Channel, version_source::VersionSource,
+};
+
+/// Environment variables holding credentials for an authenticated repository
+pub const MAVEN_USERNAME_ENV: &str = "MAVEN_USERNAME";
+pub const MAVEN_PASSWORD_ENV: &str = "MAVEN_PASSWORD";
+
+// <version> entries of maven-metadata.xml
+static METADATA_VERSION_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<version>\s*([^<]+?)\s*</version>").expect("Failed to compile metadata regex")
+});
+
+/// Well-known qualifiers in ascending order; unknown qualifiers sort after all of them
+const QUALIFIERS: [&str; 7] = ["alpha", "beta", "milestone", "rc", "snapshot", "", "sp"];
+
+/// Position of the empty (release) qualifier in `QUALIFIERS`
+const RELEASE_QUALIFIER_INDEX: usize = 5;
+
+/// Qualifiers marking a build that is not a final release, with the channel they belong to
+const UNSTABLE_QUALIFIERS: [(&str, Channel); 8] = [
+    ("alpha", Channel::Alpha),
+    ("beta", Channel::Beta),
+    ("milestone", Channel::Milestone),
+    ("rc", Channel::Rc),
+    ("snapshot", Channel::Dev),
+    ("preview", Channel::Beta),
+    ("ea", Channel::Alpha),
+    ("dev", Channel::Dev),
+];
+
+/// One item of a version as modelled by Maven's `ComparableVersion`
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Item {
+    Int(u64),
+    Str(String),
+    List(Vec<Item>),
+}
+
+impl Item {
+    fn string(value: &str, followed_by_digit: bool) -> Item {
+        let value = match value {
+            "a" if followed_by_digit => "alpha",
+            "b" if followed_by_digit => "beta",
+            "m" if followed_by_digit => "milestone",
+            "ga" | "final" | "release" => "",
+            "cr" => "rc",
+            other => other,
+        };
+        Item::Str(value.to_string())
+    }
+
+    fn parse(value: &str, is_digit: bool) -> Item {
+        if is_digit {
+            // Overlong numbers still have to sort, clamp rather than fail
+            Item::Int(value.parse().unwrap_or(u64::MAX))
+        } else {
+            Item::string(value, false)
+        }
+    }
+
+    fn is_null(&self) -> bool {
+        match self {
+            Item::Int(n) => *n == 0,
+            Item::Str(s) => s.is_empty(),
+            Item::List(items) => items.is_empty(),
+        }
+    }
+
+    fn qualifier_key(value: &str) -> String {
+        match QUALIFIERS.iter().position(|q| *q == value) {
+            Some(index) => index.to_string(),
+            None => format!("{}-{value}", QUALIFIERS.len()),
+        }
+    }
+
+    /// Compares against another item, `None` standing for a missing (padding) item
+    fn compare(&self, other: Option<&Item>) -> Ordering {
+        match (self, other) {
+            (Item::Int(n), None) => n.cmp(&0),
+            (Item::Int(a), Some(Item::Int(b))) => a.cmp(b),
+            (Item::Int(_), Some(_)) => Ordering::Greater,
+
+            (Item::Str(s), None) => {
+                Item::qualifier_key(s).cmp(&RELEASE_QUALIFIER_INDEX.to_string())
+            }
+            (Item::Str(a), Some(Item::Str(b))) => {
+                Item::qualifier_key(a).cmp(&Item::qualifier_key(b))
+            }
+            (Item::Str(_), Some(_)) => Ordering::Less,
+
+            (Item::List(items), None) => match items.first() {
+                Some(first) => first.compare(None),
+                None => Ordering::Equal,
+            },
+            (Item::List(_), Some(Item::Int(_))) => Ordering::Less,
+            (Item::List(_), Some(Item::Str(_))) => Ordering::Greater,
+            (Item::List(left), Some(Item::List(right))) => {
+                let len = left.len().max(right.len());
+                for i in 0..len {
+                    let ord = match (left.get(i), right.get(i)) {
+                        (Some(l), r) => l.compare(r),
+                        (None, Some(r)) => r.compare(None).reverse(),
+                        (None, None) => Ordering::Equal,
+                    };
+                    if ord != Ordering::Equal {
+                        return ord;
+                    }
+                }
+                Ordering::Equal
+            }
+        }
+    }
+
+    /// Collects every qualifier string in the version
+    fn qualifiers<'a>(&'a self, out: &mut Vec<&'a str>) {
+        match self {
+            Item::Str(s) => out.push(s),
+            Item::List(items) => items.iter().for_each(|item| item.qualifiers(out)),
+            Item::Int(_) => {}
+        }
+    }
+}
+
+/// Removes trailing null items, stopping at the first non-null item that is not a list
+fn normalize(items: &mut Vec<Item>) {
+    let mut i = items.len();
+    while i > 0 {
+        i -= 1;
+        if items[i].is_null() {
+            items.remove(i);
+        } else if !matches!(items[i], Item::List(_)) {
+            break;
+        }
+    }
+}
+
+/// Appends an item to the innermost list being built
+fn push_item(stack: &mut [Vec<Item>], item: Item) {
+    if let Some(list) = stack.last_mut() {
+        list.push(item);
+    }
+}
+
+/// A version ordered with the rules of Maven's `ComparableVersion`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MavenVersion {
+    items: Item,
+}
+
+impl MavenVersion {
+    pub fn parse(version: &str) -> MavenVersion {
+        let version = version.to_lowercase();
+        let chars: Vec<char> = version.chars().collect();
+
+        // Each '-' or digit/letter transition opens a nested list
+        let mut stack: Vec<Vec<Item>> = vec![vec![]];
+        let mut is_digit = false;
+        let mut start = 0;
+
+        let token = |from: usize, to: usize| chars[from..to].iter().collect::<String>();
+
+        for (i, c) in chars.iter().enumerate() {
+            match c {
+                '.' | '-' => {
+                    let item = if i == start {
+                        Item::Int(0)
+                    } else {
+                        Item::parse(&token(start, i), is_digit)
+                    };
+                    push_item(&mut stack, item);
+                    start = i + 1;
+                    if *c == '-' {
+                        stack.push(vec![]);
+                    }
+                }
+                c if c.is_ascii_digit() => {
+                    if !is_digit && i > start {
+                        let item = Item::string(&token(start, i), true);
+                        push_item(&mut stack, item);
+                        start = i;
+                        stack.push(vec![]);
+                    }
+                    is_digit = true;
+                }
+                _ => {
+                    if is_digit && i > start {
+                        let item = Item::parse(&token(start, i), true);
+                        push_item(&mut stack, item);
+                        start = i;
+                        stack.push(vec![]);
+                    }
+                    is_digit = false;
+                }
+            }
+        }
+
+        if chars.len() > start {
+            let item = Item::parse(&token(start, chars.len()), is_digit);
+            push_item(&mut stack, item);
+        }
+
+        // Fold nested lists back into their parents, normalizing each on the way
+        let mut items = stack.pop().expect("version stack is never empty");
+        normalize(&mut items);
+        while let Some(mut parent) = stack.pop() {
+            parent.push(Item::List(items));
+            normalize(&mut parent);
+            items = parent;
+        }
+
+        MavenVersion {
+            items: Item::List(items),
+        }
+    }
+
+    /// Channel of the least stable qualifier, e.g. `rc` for `2.0.0-RC1`; `-sp1`, `-jre` and
+    /// `.Final` are stable
+    pub fn channel(&self) -> Channel {
+        let mut qualifiers = Vec::new();
+        self.items.qualifiers(&mut qualifiers);
+        qualifiers
+            .iter()
+            .filter_map(|qualifier| {
+                UNSTABLE_QUALIFIERS
+                    .iter()
+                    .find(|(unstable, _)| qualifier.starts_with(unstable))
+                    .map(|(_, channel)| *channel)
+            })
+            .min()
+            .unwrap_or(Channel::Stable)
+    }
+
+    /// Snapshots, milestones and other pre-release qualifiers are not stable
+    pub fn is_stable(&self) -> bool {
+        self.channel() == Channel::Stable
+    }
+}
+
+impl Ord for MavenVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.items.compare(Some(&other.items))
+    }
+}
+
+impl PartialOrd for MavenVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Versions of an artifact in a Maven repository
+pub struct MavenSource;
+
+impl VersionSource for MavenSource {
+    fn describe(&self, target: &FileTarget) -> String {
+        format!("Maven artifact {}", target.extracted_config.tag.remote_tag)
+    }
+
+    fn list_versions(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        list_metadata_versions(target, config)
+    }
+
+    fn compare(&self, a: &str, b: &str) -> Option<Ordering> {
+        Some(MavenVersion::parse(a).cmp(&MavenVersion::parse(b)))
+    }
+
+    fn channel(&self, version: &str) -> Option<Channel> {
+        version
+            .starts_with(|c: char| c.is_ascii_digit())
+            .then(|| MavenVersion::parse(version).channel())
+    }
+
+    fn latest(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        get_latest_tag_from_maven(target, config)
+    }
+}
+
+/// Lists every version from the artifact's `maven-metadata.xml`
+fn list_metadata_versions(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let coordinates = &target.extracted_config.tag.remote_tag;
+    let (group, artifact) = coordinates
+        .split_once(':')
+        .ok_or_else(|| format!("Expected group:artifact coordinates, got {coordinates}"))?;
+
+    let url = format!(
+        "{}/{}/{artifact}/maven-metadata.xml",
+        config.maven.url.trim_end_matches('/'),
+        group.replace('.', "/")
+    );
+    let headers: Vec<(&str, String)> =
+        http::basic_credentials(MAVEN_USERNAME_ENV, MAVEN_PASSWORD_ENV)
+            .map(|credentials| vec![("Authorization", credentials)])
+            .unwrap_or_default();
+
+    let metadata = http::get(&url, &headers)?
+        .ok_or_else(|| format!("Artifact {coordinates} not found at {url}"))?
+        .into_string()?;
+
+    Ok(METADATA_VERSION_RE
+        .captures_iter(&metadata)
+        .map(|caps| caps[1].to_string())
+        .collect())
+}
+
+pub fn get_latest_tag_from_maven(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let coordinates = &target.extracted_config.tag.remote_tag;
+
+    list_metadata_versions(target, config)?
+        .into_iter()
+        .map(|version| (MavenVersion::parse(&version), version))
+        .filter(|(parsed, _)| parsed.is_stable())
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, version)| version)
+        .ok_or_else(|| format!("No stable versions found for {coordinates}").into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(version: &str) -> MavenVersion {
+        MavenVersion::parse(version)
+    }
+
+    #[test]
+    fn qualifiers_order_around_the_release() {
+        let versions = [
+            "1.0-alpha",
+            "1.0-beta",
+            "1.0-milestone",
+            "1.0-rc",
+            "1.0-SNAPSHOT",
+            "1.0",
+            "1.0-sp",
+        ];
+        for pair in versions.windows(2) {
+            assert!(parse(pair[0]) < parse(pair[1]), "{} < {}", pair[0], pair[1]);
+        }
+        assert!(parse("1.0-sp") < parse("1.0.1"));
+        assert!(parse("1.0-rc1") < parse("1.0-rc2"));
+        assert!(parse("1.0-alpha-2") < parse("1.0-beta-1"));
+    }
+
+    #[test]
+    fn trailing_zeros_and_aliases_are_equal() {
+        assert_eq!(parse("1.0"), parse("1.0.0"));
+        assert_eq!(parse("1"), parse("1.0.0"));
+        assert_eq!(parse("1.0-ga"), parse("1.0"));
+        assert_eq!(parse("1.0.Final"), parse("1.0"));
+        assert_eq!(parse("1.0-cr1"), parse("1.0-rc1"));
+        assert_eq!(parse("1.0a1"), parse("1.0-alpha-1"));
+    }
+
+    #[test]
+    fn qualifiers_ignore_case() {
+        assert_eq!(parse("1.0-RC1"), parse("1.0-rc1"));
+        assert_eq!(parse("1.0-Snapshot"), parse("1.0-SNAPSHOT"));
+        assert_eq!(parse("1.0-ALPHA"), parse("1.0-alpha"));
+    }
+
+    #[test]
+    fn channels_follow_the_least_stable_qualifier() {
+        assert_eq!(parse("2.0.0-RC1").channel(), Channel::Rc);
+        assert_eq!(parse("2.0.0-M3").channel(), Channel::Milestone);
+        assert_eq!(parse("2.0.0-SNAPSHOT").channel(), Channel::Dev);
+        assert!(parse("33.0.0-jre").is_stable());
+        assert!(parse("1.0-sp1").is_stable());
+        assert!(parse("5.6.15.Final").is_stable());
+    }
+}
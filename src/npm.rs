@@ -0,0 +1,175 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::{
+    cup_parser::FileTarget,
+    http,
+    init::Config,
+    version_source::{Release, VersionSource},
+};
+
+/// Environment variable holding a registry token when no `.npmrc` provides one
+pub const NPM_TOKEN_ENV: &str = "NPM_TOKEN";
+
+/// Dist-tag used when the annotation does not name one
+const DEFAULT_DIST_TAG: &str = "latest";
+
+#[derive(Debug, Deserialize)]
+struct Packument {
+    #[serde(rename = "dist-tags", default)]
+    dist_tags: HashMap<String, String>,
+    #[serde(default)]
+    versions: HashMap<String, serde::de::IgnoredAny>,
+    /// Publish time per version, only in the full packument
+    #[serde(default)]
+    time: HashMap<String, String>,
+}
+
+/// Splits `name@tag` into package name and dist-tag, keeping the `@` of scoped packages
+fn split_dist_tag(spec: &str) -> (&str, &str) {
+    match spec.rfind('@') {
+        Some(pos) if pos > 0 => (&spec[..pos], &spec[pos + 1..]),
+        _ => (spec, DEFAULT_DIST_TAG),
+    }
+}
+
+/// Finds an `_authToken` for the registry in the project or user `.npmrc`
+fn npmrc_token(registry: &str) -> Option<String> {
+    // .npmrc keys drop the scheme, e.g. //registry.example.com/:_authToken=...
+    let key_prefix = registry
+        .split_once("://")
+        .map(|(_, rest)| rest)
+        .unwrap_or(registry)
+        .trim_end_matches('/');
+
+    let mut candidates = vec![PathBuf::from(".npmrc")];
+    if let Some(home) = std::env::var_os("HOME") {
+        candidates.push(PathBuf::from(home).join(".npmrc"));
+    }
+
+    candidates
+        .into_iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .find_map(|content| {
+            content.lines().find_map(|line| {
+                let (key, value) = line.trim().split_once('=')?;
+                let scope = key.trim().strip_suffix(":_authToken")?;
+                let scope = scope.trim_start_matches('/').trim_end_matches('/');
+                (scope == key_prefix).then(|| http::expand_env(value.trim()))
+            })
+        })
+}
+
+/// Versions and dist-tags of a package in an npm registry
+pub struct NpmSource;
+
+impl VersionSource for NpmSource {
+    fn describe(&self, target: &FileTarget) -> String {
+        format!("npm package {}", target.extracted_config.tag.remote_tag)
+    }
+
+    /// Lists every published version, or only the dist-tag's version when one is named
+    fn list_versions(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let spec = &target.extracted_config.tag.remote_tag;
+        let (package, dist_tag) = split_dist_tag(spec);
+        let packument = fetch_packument(package, config, false)?;
+
+        if package.len() < spec.len() {
+            return Ok(packument
+                .dist_tags
+                .get(dist_tag)
+                .into_iter()
+                .cloned()
+                .collect());
+        }
+
+        Ok(packument.versions.into_keys().collect())
+    }
+
+    fn list_releases(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+        Ok(self.list_releases_and_latest(target, config)?.0)
+    }
+
+    /// Same versions as `list_versions`, timed from the full packument, with the dist-tag's
+    /// version as the pick
+    fn list_releases_and_latest(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<(Vec<Release>, Option<String>), Box<dyn std::error::Error>> {
+        let spec = &target.extracted_config.tag.remote_tag;
+        let (package, dist_tag) = split_dist_tag(spec);
+        let packument = fetch_packument(package, config, true)?;
+        let latest = packument.dist_tags.get(dist_tag).cloned();
+
+        let versions: Vec<String> = if package.len() < spec.len() {
+            latest.iter().cloned().collect()
+        } else {
+            packument.versions.into_keys().collect()
+        };
+
+        let releases = versions
+            .into_iter()
+            .map(|version| {
+                let published = packument.time.get(&version).cloned();
+                Release::new(version, published.as_deref())
+            })
+            .collect();
+        Ok((releases, latest))
+    }
+
+    fn latest(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        get_latest_tag_from_npm(target, config)
+    }
+}
+
+/// Fetches the abbreviated packument, or the full one that also carries publish times
+fn fetch_packument(
+    package: &str,
+    config: &Config,
+    full: bool,
+) -> Result<Packument, Box<dyn std::error::Error>> {
+    let registry = config.npm.registry_base();
+    let url = format!("{registry}/{}", package.replace('/', "%2f"));
+
+    let accept = if full {
+        "application/json"
+    } else {
+        "application/vnd.npm.install-v1+json"
+    };
+    let mut headers = vec![("Accept", accept.to_string())];
+    let token = npmrc_token(registry).or_else(|| std::env::var(NPM_TOKEN_ENV).ok());
+    if let Some(token) = token.filter(|token| !token.is_empty()) {
+        headers.push(("Authorization", format!("Bearer {token}")));
+    }
+
+    http::get_json::<Packument>(&url, &headers)?
+        .ok_or_else(|| format!("Package {package} not found in {registry}").into())
+}
+
+pub fn get_latest_tag_from_npm(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let (package, dist_tag) = split_dist_tag(&target.extracted_config.tag.remote_tag);
+    let packument = fetch_packument(package, config, false)?;
+
+    packument
+        .dist_tags
+        .get(dist_tag)
+        .cloned()
+        .ok_or_else(|| format!("Package {package} has no dist-tag {dist_tag}").into())
+}
@@ -0,0 +1,142 @@
+use serde::Deserialize;
+
+use crate::{cup_parser::FileTarget, http, init::Config, version, version_source::VersionSource};
+
+/// Service index resources serving package registrations, in order of preference; only
+/// the gzipped 3.6.0 hive includes SemVer 2.0.0 versions
+const REGISTRATIONS_BASE_URL_TYPES: [&str; 3] = [
+    "RegistrationsBaseUrl/3.6.0",
+    "RegistrationsBaseUrl/3.4.0",
+    "RegistrationsBaseUrl",
+];
+
+#[derive(Debug, Deserialize)]
+struct ServiceIndex {
+    resources: Vec<Resource>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Resource {
+    #[serde(rename = "@id")]
+    id: String,
+    #[serde(rename = "@type")]
+    kind: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistrationIndex {
+    items: Vec<RegistrationPage>,
+}
+
+/// A page of a registration index, whose leaves are left out for packages with many versions
+#[derive(Debug, Deserialize)]
+struct RegistrationPage {
+    #[serde(rename = "@id")]
+    id: String,
+    #[serde(default)]
+    items: Option<Vec<RegistrationLeaf>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RegistrationLeaf {
+    catalog_entry: CatalogEntry,
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogEntry {
+    version: String,
+    /// Unlisted versions stay downloadable but are hidden from search and updates
+    #[serde(default = "listed_default")]
+    listed: bool,
+}
+
+fn listed_default() -> bool {
+    true
+}
+
+/// Versions of a package in a NuGet V3 feed
+pub struct NuGetSource;
+
+impl VersionSource for NuGetSource {
+    fn describe(&self, target: &FileTarget) -> String {
+        format!("NuGet package {}", target.extracted_config.tag.remote_tag)
+    }
+
+    fn list_versions(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        list_package_versions(target, config)
+    }
+
+    fn latest(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        get_latest_tag_from_nuget(target, config)
+    }
+}
+
+/// Lists every listed version of a package through the registration API
+fn list_package_versions(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let package = &target.extracted_config.tag.remote_tag;
+    let service_index_url = &config.nuget.url;
+
+    // The service index tells where the registrations live
+    let index = http::get_json::<ServiceIndex>(service_index_url, &[])?
+        .ok_or_else(|| format!("NuGet service index {service_index_url} not found"))?;
+    let base_url = REGISTRATIONS_BASE_URL_TYPES
+        .iter()
+        .find_map(|kind| {
+            index
+                .resources
+                .iter()
+                .find(|resource| resource.kind == *kind)
+        })
+        .map(|resource| resource.id.trim_end_matches('/'))
+        .ok_or_else(|| format!("{service_index_url} has no RegistrationsBaseUrl resource"))?;
+
+    let url = format!("{base_url}/{}/index.json", package.to_lowercase());
+    let registration = http::get_json::<RegistrationIndex>(&url, &[])?
+        .ok_or_else(|| format!("Package {package} not found in {service_index_url}"))?;
+
+    let mut versions = Vec::new();
+    for page in registration.items {
+        let leaves = match page.items {
+            Some(leaves) => leaves,
+            None => http::get_json::<RegistrationPage>(&page.id, &[])?
+                .and_then(|page| page.items)
+                .ok_or_else(|| format!("Registration page {} not found", page.id))?,
+        };
+        versions.extend(
+            leaves
+                .into_iter()
+                .filter(|leaf| leaf.catalog_entry.listed)
+                // Build metadata is not part of the version, as in the flat container API
+                .map(|leaf| match leaf.catalog_entry.version.split_once('+') {
+                    Some((version, _)) => version.to_string(),
+                    None => leaf.catalog_entry.version,
+                }),
+        );
+    }
+
+    Ok(versions)
+}
+
+pub fn get_latest_tag_from_nuget(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let package = &target.extracted_config.tag.remote_tag;
+    let versions = list_package_versions(target, config)?;
+
+    version::highest_stable(&versions)
+        .map(str::to_string)
+        .ok_or_else(|| format!("No stable versions found for package {package}").into())
+}
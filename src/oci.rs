@@ -0,0 +1,337 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    cup_parser::FileTarget, http, init::Config, version::Version, version_source::VersionSource,
+};
+
+/// Environment variables holding registry credentials used to obtain a token
+pub const OCI_USERNAME_ENV: &str = "OCI_USERNAME";
+pub const OCI_PASSWORD_ENV: &str = "OCI_PASSWORD";
+
+/// Media type requested when fetching a manifest
+const OCI_MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+
+/// Registry serving images referenced without a registry host
+const DOCKER_HUB_REGISTRY: &str = "registry-1.docker.io";
+
+// key="value" pairs of a WWW-Authenticate challenge
+static CHALLENGE_PARAM_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(\w+)="([^"]*)""#).expect("Failed to compile challenge param regex")
+});
+
+#[derive(Debug, Deserialize)]
+struct TagList {
+    tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    token: Option<String>,
+    access_token: Option<String>,
+}
+
+/// An image location such as `ghcr.io/owner/app:alpine` split into its parts
+#[derive(Debug, PartialEq)]
+pub struct ImageReference {
+    /// Scheme and host of the registry, e.g. `https://ghcr.io`
+    base: String,
+    /// Repository name inside the registry, e.g. `owner/app`
+    name: String,
+    /// Tag variant to follow, e.g. `alpine` for tags like `1.2.3-alpine`
+    variant: Option<String>,
+}
+
+impl ImageReference {
+    pub fn parse(location: &str) -> Option<ImageReference> {
+        let (scheme, rest) = match location.split_once("://") {
+            Some((scheme, rest)) => (scheme, rest),
+            None => ("https", location),
+        };
+
+        let (path, variant) = match rest.rsplit_once('/') {
+            Some((head, last)) => match last.split_once(':') {
+                Some((last, variant)) => (format!("{head}/{last}"), Some(variant.to_string())),
+                None => (rest.to_string(), None),
+            },
+            None => match rest.split_once(':') {
+                Some((name, variant)) => (name.to_string(), Some(variant.to_string())),
+                None => (rest.to_string(), None),
+            },
+        };
+
+        // The first component is a registry host if it looks like one
+        let (host, name) = match path.split_once('/') {
+            Some((first, name))
+                if first.contains('.') || first.contains(':') || first == "localhost" =>
+            {
+                (first.to_string(), name.to_string())
+            }
+            _ => ("docker.io".to_string(), path),
+        };
+
+        if name.is_empty() {
+            return None;
+        }
+
+        let (host, name) = match host.as_str() {
+            "docker.io" | "index.docker.io" if !name.contains('/') => {
+                (DOCKER_HUB_REGISTRY.to_string(), format!("library/{name}"))
+            }
+            "docker.io" | "index.docker.io" => (DOCKER_HUB_REGISTRY.to_string(), name),
+            _ => (host, name),
+        };
+
+        Some(ImageReference {
+            base: format!("{scheme}://{host}"),
+            name,
+            variant: variant.filter(|v| !v.is_empty()),
+        })
+    }
+}
+
+/// Answers a `WWW-Authenticate` challenge, returning the `Authorization` header to retry with
+fn authorize(
+    challenge: &str,
+    image: &ImageReference,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let (kind, params) = challenge.split_once(' ').unwrap_or((challenge, ""));
+
+    if kind.eq_ignore_ascii_case("basic") {
+        return http::basic_credentials(OCI_USERNAME_ENV, OCI_PASSWORD_ENV).ok_or_else(|| {
+            format!("{} requires credentials in {OCI_USERNAME_ENV}", image.base).into()
+        });
+    }
+
+    if !kind.eq_ignore_ascii_case("bearer") {
+        return Err(format!(
+            "Unsupported authentication challenge from {}: {challenge}",
+            image.base
+        )
+        .into());
+    }
+
+    let mut realm = None;
+    let mut query = Vec::new();
+    for caps in CHALLENGE_PARAM_RE.captures_iter(params) {
+        match &caps[1] {
+            "realm" => realm = Some(caps[2].to_string()),
+            key => query.push(format!("{key}={}", &caps[2])),
+        }
+    }
+    if !query.iter().any(|param| param.starts_with("scope=")) {
+        query.push(format!("scope=repository:{}:pull", image.name));
+    }
+
+    let realm =
+        realm.ok_or_else(|| format!("Bearer challenge from {} has no realm", image.base))?;
+    let token_url = format!("{realm}?{}", query.join("&"));
+    let headers: Vec<(&str, String)> = http::basic_credentials(OCI_USERNAME_ENV, OCI_PASSWORD_ENV)
+        .map(|credentials| vec![("Authorization", credentials)])
+        .unwrap_or_default();
+
+    let response = http::get_json::<TokenResponse>(&token_url, &headers)?
+        .ok_or_else(|| format!("Token endpoint {realm} not found"))?;
+    let token = response
+        .token
+        .or(response.access_token)
+        .ok_or_else(|| format!("Token endpoint {realm} returned no token"))?;
+
+    Ok(format!("Bearer {token}"))
+}
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    config: Descriptor,
+}
+
+#[derive(Debug, Deserialize)]
+struct Descriptor {
+    digest: String,
+}
+
+/// Makes registry requests for one image, keeping the token once a challenge was answered
+pub struct RegistryClient<'a> {
+    image: &'a ImageReference,
+    authorization: Option<String>,
+}
+
+impl<'a> RegistryClient<'a> {
+    pub fn new(image: &'a ImageReference) -> Self {
+        RegistryClient {
+            image,
+            authorization: None,
+        }
+    }
+
+    /// Performs a GET request against the registry, authenticating on the first 401
+    fn get(
+        &mut self,
+        url: &str,
+        accept: Option<&str>,
+    ) -> Result<ureq::Response, Box<dyn std::error::Error>> {
+        loop {
+            let mut headers: Vec<(&str, String)> = self
+                .authorization
+                .iter()
+                .map(|value| ("Authorization", value.clone()))
+                .collect();
+            if let Some(accept) = accept {
+                headers.push(("Accept", accept.to_string()));
+            }
+
+            match http::call(url, &headers).map_err(|e| *e) {
+                Ok(response) => return Ok(response),
+                Err(ureq::Error::Status(401, response)) if self.authorization.is_none() => {
+                    let challenge = response.header("www-authenticate").ok_or_else(|| {
+                        format!("{url} requires authentication but sent no challenge")
+                    })?;
+                    self.authorization = Some(authorize(challenge, self.image)?);
+                }
+                Err(ureq::Error::Status(404, _)) => {
+                    return Err(format!(
+                        "{} not found in {} ({url})",
+                        self.image.name, self.image.base
+                    )
+                    .into());
+                }
+                Err(ureq::Error::Status(code, response)) => {
+                    return Err(http::status_error(url, code, response).into());
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Lists every tag of the image, following `Link` pagination
+    pub fn list_tags(&mut self) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let base = &self.image.base;
+        let mut url = format!("{base}/v2/{}/tags/list?n=1000", self.image.name);
+        let mut tags = Vec::new();
+
+        loop {
+            let response = self.get(&url, None)?;
+
+            let next = http::next_link(&response);
+            let page: TagList = response.into_json()?;
+            tags.extend(page.tags.unwrap_or_default());
+
+            match next {
+                Some(next) if next.starts_with('/') => url = format!("{base}{next}"),
+                Some(next) => url = next,
+                None => break,
+            }
+        }
+
+        Ok(tags)
+    }
+
+    /// Downloads the config blob of a tagged manifest, e.g. `Chart.yaml` data of a Helm chart
+    pub fn config_blob<T: DeserializeOwned>(
+        &mut self,
+        tag: &str,
+    ) -> Result<T, Box<dyn std::error::Error>> {
+        let base = &self.image.base;
+        let name = &self.image.name;
+
+        let manifest_url = format!("{base}/v2/{name}/manifests/{tag}");
+        let manifest: Manifest = self
+            .get(&manifest_url, Some(OCI_MANIFEST_MEDIA_TYPE))?
+            .into_json()?;
+
+        let blob_url = format!("{base}/v2/{name}/blobs/{}", manifest.config.digest);
+        Ok(self.get(&blob_url, None)?.into_json()?)
+    }
+}
+
+/// Parses the version of a tag belonging to a variant, skipping named tags such as "latest"
+fn variant_version(tag: &str, suffix: Option<&str>) -> Option<Version> {
+    let core = match suffix {
+        Some(suffix) => tag.strip_suffix(suffix)?,
+        None => tag,
+    };
+
+    if !core
+        .trim_start_matches('v')
+        .starts_with(|c: char| c.is_ascii_digit())
+    {
+        return None;
+    }
+
+    Version::parse(core)
+}
+
+/// Picks the highest stable version tag, restricted to `-<variant>` tags when a variant is given
+pub fn select_tag(tags: &[String], variant: Option<&str>) -> Option<String> {
+    let suffix = variant.map(|variant| format!("-{variant}"));
+
+    tags.iter()
+        .filter_map(|tag| {
+            let version = variant_version(tag, suffix.as_deref())?;
+            version.is_stable().then_some((version, tag))
+        })
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, tag)| tag.clone())
+}
+
+/// Tags of a container image in an OCI registry
+pub struct OciSource;
+
+impl VersionSource for OciSource {
+    fn describe(&self, target: &FileTarget) -> String {
+        format!("image {}", target.extracted_config.tag.remote_tag)
+    }
+
+    fn list_versions(
+        &self,
+        target: &FileTarget,
+        _config: &Config,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let location = &target.extracted_config.tag.remote_tag;
+        let image = ImageReference::parse(location)
+            .ok_or_else(|| format!("Invalid image reference {location}"))?;
+        let suffix = image.variant.as_ref().map(|variant| format!("-{variant}"));
+
+        Ok(RegistryClient::new(&image)
+            .list_tags()?
+            .into_iter()
+            .filter(|tag| variant_version(tag, suffix.as_deref()).is_some())
+            .collect())
+    }
+
+    fn version_of<'a>(&self, target: &FileTarget, tag: &'a str) -> &'a str {
+        let variant = ImageReference::parse(&target.extracted_config.tag.remote_tag)
+            .and_then(|image| image.variant);
+        match variant {
+            Some(variant) => tag
+                .strip_suffix(variant.as_str())
+                .and_then(|core| core.strip_suffix('-'))
+                .unwrap_or(tag),
+            None => tag,
+        }
+    }
+
+    fn latest(
+        &self,
+        target: &FileTarget,
+        _config: &Config,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        get_latest_tag_from_oci(target)
+    }
+}
+
+pub fn get_latest_tag_from_oci(target: &FileTarget) -> Result<String, Box<dyn std::error::Error>> {
+    let location = &target.extracted_config.tag.remote_tag;
+    let image = ImageReference::parse(location)
+        .ok_or_else(|| format!("Invalid image reference {location}"))?;
+
+    let tags = RegistryClient::new(&image).list_tags()?;
+    if tags.is_empty() {
+        return Err(format!("No tags found for image {location}").into());
+    }
+
+    select_tag(&tags, image.variant.as_deref())
+        .ok_or_else(|| format!("No version tags found for image {location}").into())
+}
@@ -0,0 +1,102 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::{
+    cup_parser::FileTarget,
+    http,
+    init::Config,
+    version,
+    version_source::{Release, VersionSource},
+};
+
+#[derive(Debug, Deserialize)]
+struct PackageMetadata {
+    packages: HashMap<String, Vec<PackageVersion>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PackageVersion {
+    version: String,
+    #[serde(default)]
+    time: Option<String>,
+}
+
+/// Tagged releases of a Composer package on Packagist
+pub struct PackagistSource;
+
+impl VersionSource for PackagistSource {
+    fn describe(&self, target: &FileTarget) -> String {
+        format!(
+            "Composer package {}",
+            target.extracted_config.tag.remote_tag
+        )
+    }
+
+    fn list_versions(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        Ok(list_tagged_versions(target, config)?
+            .into_iter()
+            .map(|release| release.tag)
+            .collect())
+    }
+
+    fn list_releases(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+        list_tagged_versions(target, config)
+    }
+
+    fn latest(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        get_latest_tag_from_packagist(target, config)
+    }
+}
+
+fn list_tagged_versions(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+    let package = target.extracted_config.tag.remote_tag.to_lowercase();
+    let url = format!(
+        "{}/p2/{package}.json",
+        config.packagist.url.trim_end_matches('/')
+    );
+
+    let mut metadata = http::get_json::<PackageMetadata>(&url, &[])?
+        .ok_or_else(|| format!("Package {package} not found at {url}"))?;
+    let versions = metadata
+        .packages
+        .remove(&package)
+        .ok_or_else(|| format!("Package {package} missing from {url}"))?;
+
+    // Tagged releases only, dev branches are not versions
+    Ok(versions
+        .into_iter()
+        .filter(|entry| !entry.version.starts_with("dev-") && !entry.version.ends_with("-dev"))
+        .map(|entry| Release::new(entry.version, entry.time.as_deref()))
+        .collect())
+}
+
+pub fn get_latest_tag_from_packagist(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let package = &target.extracted_config.tag.remote_tag;
+    let versions: Vec<String> = list_tagged_versions(target, config)?
+        .into_iter()
+        .map(|release| release.tag)
+        .collect();
+
+    // Alpha, beta and RC builds are not stable
+    version::highest_stable(&versions)
+        .map(str::to_string)
+        .ok_or_else(|| format!("No stable versions found for package {package}").into())
+}
@@ -0,0 +1,175 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::{
+    command::run_with_timeout,
+    cup_parser::FileTarget,
+    init::{Config, PluginSource},
+    version,
+    version_source::{Release, VersionSource},
+};
+
+/// Version of the JSON request/response protocol spoken with plugins
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Prefix of plugin executables looked up on PATH, e.g. `cup-provider-artifactory`
+pub const EXECUTABLE_PREFIX: &str = "cup-provider-";
+
+/// Request written to the plugin's stdin
+#[derive(Debug, Serialize)]
+struct PluginRequest<'a> {
+    protocol: u32,
+    /// Name the plugin is registered under, e.g. `Artifactory`
+    remote: &'a str,
+    /// Positional annotation arguments, e.g. `["tools/terraform"]`
+    arguments: Vec<&'a str>,
+    /// Options from `cup.toml`, overridden by annotation `key=value` arguments
+    options: Map<String, Value>,
+    /// Annotated file and 1-based line, for plugins that resolve paths
+    file: String,
+    line: i128,
+}
+
+/// Response read from the plugin's stdout
+#[derive(Debug, Deserialize)]
+struct PluginResponse {
+    #[serde(default)]
+    versions: Vec<String>,
+    /// Version the plugin recommends, the highest stable candidate when absent
+    #[serde(default)]
+    latest: Option<String>,
+    /// RFC 3339 publish time per version, for `min_age`
+    #[serde(default)]
+    published: HashMap<String, String>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Versions served by an external `cup-provider-<name>` program over JSON on stdin/stdout
+pub struct PluginProvider {
+    name: String,
+}
+
+impl PluginProvider {
+    pub fn new(name: &str) -> Self {
+        PluginProvider {
+            name: name.to_string(),
+        }
+    }
+
+    fn settings<'a>(&self, config: &'a Config) -> Result<&'a PluginSource, String> {
+        config
+            .plugin
+            .get(&self.name)
+            .ok_or_else(|| format!("No [plugin.{}] configured in cup.toml", self.name))
+    }
+
+    fn executable(&self, settings: &PluginSource) -> String {
+        settings
+            .path
+            .clone()
+            .unwrap_or_else(|| format!("{EXECUTABLE_PREFIX}{}", self.name.to_lowercase()))
+    }
+
+    /// Sends the request for a target and parses what the plugin answers
+    fn call(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<PluginResponse, Box<dyn std::error::Error>> {
+        let settings = self.settings(config)?;
+        let executable = self.executable(settings);
+
+        let mut options = settings.options.clone();
+        for (key, value) in &target.options {
+            options.insert(key.clone(), Value::String(value.clone()));
+        }
+
+        let request = PluginRequest {
+            protocol: PROTOCOL_VERSION,
+            remote: &self.name,
+            arguments: target
+                .extracted_config
+                .tag
+                .remote_tag
+                .split_whitespace()
+                .collect(),
+            options,
+            file: target.file.full_path.to_string_lossy().to_string(),
+            line: target.row + 1,
+        };
+
+        let output = run_with_timeout(
+            std::slice::from_ref(&executable),
+            Some(&serde_json::to_string(&request)?),
+            Duration::from_secs(settings.timeout_secs),
+        )?;
+        let response: PluginResponse = serde_json::from_str(output.trim())
+            .map_err(|e| format!("{executable} returned an invalid response: {e}"))?;
+
+        match response.error {
+            Some(error) => Err(format!("{executable}: {error}").into()),
+            None => Ok(response),
+        }
+    }
+}
+
+impl VersionSource for PluginProvider {
+    fn describe(&self, target: &FileTarget) -> String {
+        format!(
+            "plugin {} {}",
+            self.name, target.extracted_config.tag.remote_tag
+        )
+    }
+
+    fn list_versions(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        Ok(self.call(target, config)?.versions)
+    }
+
+    fn list_releases(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+        Ok(self.list_releases_and_latest(target, config)?.0)
+    }
+
+    /// Releases and the plugin's recommended version from a single run
+    fn list_releases_and_latest(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<(Vec<Release>, Option<String>), Box<dyn std::error::Error>> {
+        let response = self.call(target, config)?;
+        let latest = response
+            .latest
+            .or_else(|| version::highest_stable(&response.versions).map(str::to_string));
+        let releases = response
+            .versions
+            .into_iter()
+            .map(|version| {
+                let published = response.published.get(&version).cloned();
+                Release::new(version, published.as_deref())
+            })
+            .collect();
+        Ok((releases, latest))
+    }
+
+    fn latest(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let response = self.call(target, config)?;
+        response
+            .latest
+            .or_else(|| version::highest_stable(&response.versions).map(str::to_string))
+            .ok_or_else(|| format!("No stable versions found for {}", self.describe(target)).into())
+    }
+}
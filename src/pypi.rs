@@ -0,0 +1,382 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::{
+    cup_parser::FileTarget,
+    http,
+    init::Config,
+    version::Channel,
+    version_source::{Release, VersionSource},
+};
+
+// Version scheme from PEP 440, appendix B
+static PEP440_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?ix)^\s*v?
+        (?:(?P<epoch>[0-9]+)!)?
+        (?P<release>[0-9]+(?:\.[0-9]+)*)
+        (?:[-_.]?(?P<pre_l>alpha|beta|preview|pre|rc|a|b|c)[-_.]?(?P<pre_n>[0-9]+)?)?
+        (?:-(?P<post_n1>[0-9]+)|[-_.]?(?P<post_l>post|rev|r)[-_.]?(?P<post_n2>[0-9]+)?)?
+        (?:[-_.]?(?P<dev_l>dev)[-_.]?(?P<dev_n>[0-9]+)?)?
+        (?:\+(?P<local>[a-z0-9]+(?:[-_.][a-z0-9]+)*))?
+        \s*$",
+    )
+    .expect("Failed to compile PEP 440 regex")
+});
+
+/// Sort key for an optional version segment, with explicit "before all" and "after all" slots
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Segment {
+    Lowest,
+    Value(u8, u64),
+    Highest,
+}
+
+/// A version parsed and ordered according to PEP 440
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pep440Version {
+    epoch: u64,
+    release: Vec<u64>,
+    pre: Option<(u8, u64)>,
+    post: Option<u64>,
+    dev: Option<u64>,
+    local: Option<String>,
+}
+
+impl Pep440Version {
+    pub fn parse(input: &str) -> Option<Pep440Version> {
+        let caps = PEP440_RE.captures(input)?;
+        let number = |name: &str| caps.name(name).and_then(|m| m.as_str().parse::<u64>().ok());
+
+        let mut release = caps["release"]
+            .split('.')
+            .map(|n| n.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        // Trailing zeros are insignificant: 1.0 == 1.0.0
+        while release.len() > 1 && release.last() == Some(&0) {
+            release.pop();
+        }
+
+        let pre = caps.name("pre_l").map(|label| {
+            let kind = match label.as_str().to_lowercase().as_str() {
+                "a" | "alpha" => 0,
+                "b" | "beta" => 1,
+                _ => 2,
+            };
+            (kind, number("pre_n").unwrap_or(0))
+        });
+
+        let post = if caps.name("post_n1").is_some() {
+            number("post_n1")
+        } else {
+            caps.name("post_l").map(|_| number("post_n2").unwrap_or(0))
+        };
+
+        Some(Pep440Version {
+            epoch: number("epoch").unwrap_or(0),
+            release,
+            pre,
+            post,
+            dev: caps.name("dev_l").map(|_| number("dev_n").unwrap_or(0)),
+            local: caps.name("local").map(|m| m.as_str().to_lowercase()),
+        })
+    }
+
+    /// Pre-releases are alpha, beta, release candidate and development releases
+    pub fn is_prerelease(&self) -> bool {
+        self.pre.is_some() || self.dev.is_some()
+    }
+
+    /// Post-releases and epochs are stable, `1.0a1.dev1` is a development release
+    pub fn channel(&self) -> Channel {
+        match (self.pre, self.dev) {
+            (_, Some(_)) => Channel::Dev,
+            (Some((0, _)), None) => Channel::Alpha,
+            (Some((1, _)), None) => Channel::Beta,
+            (Some(_), None) => Channel::Rc,
+            (None, None) => Channel::Stable,
+        }
+    }
+
+    fn pre_key(&self) -> Segment {
+        match (self.pre, self.post, self.dev) {
+            // 1.0.dev0 sorts before 1.0a0
+            (None, None, Some(_)) => Segment::Lowest,
+            (None, _, _) => Segment::Highest,
+            (Some((kind, n)), _, _) => Segment::Value(kind, n),
+        }
+    }
+
+    fn post_key(&self) -> Segment {
+        self.post.map_or(Segment::Lowest, |n| Segment::Value(0, n))
+    }
+
+    fn dev_key(&self) -> Segment {
+        self.dev.map_or(Segment::Highest, |n| Segment::Value(0, n))
+    }
+}
+
+impl Ord for Pep440Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.epoch
+            .cmp(&other.epoch)
+            .then_with(|| self.release.cmp(&other.release))
+            .then_with(|| self.pre_key().cmp(&other.pre_key()))
+            .then_with(|| self.post_key().cmp(&other.post_key()))
+            .then_with(|| self.dev_key().cmp(&other.dev_key()))
+            .then_with(|| self.local.cmp(&other.local))
+    }
+}
+
+impl PartialOrd for Pep440Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonProject {
+    releases: HashMap<String, Vec<JsonFile>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonFile {
+    #[serde(default)]
+    yanked: bool,
+    #[serde(default)]
+    upload_time_iso_8601: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SimpleProject {
+    #[serde(default)]
+    versions: Vec<String>,
+    files: Vec<SimpleFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SimpleFile {
+    filename: String,
+    /// `false`, `true` or a string with the reason for yanking
+    #[serde(default)]
+    yanked: serde_json::Value,
+}
+
+impl SimpleFile {
+    fn is_yanked(&self) -> bool {
+        !matches!(
+            self.yanked,
+            serde_json::Value::Null | serde_json::Value::Bool(false)
+        )
+    }
+
+    /// Extracts the version from a wheel (`name-1.0-py3-none-any.whl`) or sdist (`name-1.0.tar.gz`)
+    fn version(&self) -> Option<&str> {
+        if let Some(stem) = self.filename.strip_suffix(".whl") {
+            return stem.split('-').nth(1);
+        }
+        let stem = [".tar.gz", ".tar.bz2", ".zip", ".tgz"]
+            .iter()
+            .find_map(|ext| self.filename.strip_suffix(ext))?;
+        stem.rsplit_once('-').map(|(_, version)| version)
+    }
+}
+
+/// Normalizes a project name as described in PEP 503
+fn normalize_name(name: &str) -> String {
+    name.to_lowercase()
+        .split(['-', '_', '.'])
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Lists non-yanked versions through the JSON API at `<url>/pypi/<name>/json`, published when
+/// their first file was uploaded
+fn list_versions_json(
+    name: &str,
+    config: &Config,
+) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+    let url = format!("{}/pypi/{name}/json", config.pypi.url.trim_end_matches('/'));
+    let project = http::get_json::<JsonProject>(&url, &[])?
+        .ok_or_else(|| format!("Project {name} not found at {url}"))?;
+
+    Ok(project
+        .releases
+        .into_iter()
+        .filter(|(_, files)| !files.is_empty() && !files.iter().all(|file| file.yanked))
+        .map(|(version, files)| {
+            let published = files
+                .iter()
+                .filter_map(|file| file.upload_time_iso_8601.as_deref())
+                .min();
+            Release::new(version, published)
+        })
+        .collect())
+}
+
+/// Lists non-yanked versions through the PEP 691 JSON simple API
+fn list_versions_simple(
+    name: &str,
+    simple_url: &str,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let url = format!("{}/{name}/", simple_url.trim_end_matches('/'));
+    let headers = [("Accept", "application/vnd.pypi.simple.v1+json".to_string())];
+    let project = http::get_json::<SimpleProject>(&url, &headers)?
+        .ok_or_else(|| format!("Project {name} not found at {url}"))?;
+
+    // A version counts as yanked only when every one of its files is
+    let mut available: HashMap<String, bool> = HashMap::new();
+    for file in &project.files {
+        if let Some(version) = file.version() {
+            *available.entry(version.to_string()).or_default() |= !file.is_yanked();
+        }
+    }
+
+    let versions = if project.versions.is_empty() {
+        available.keys().cloned().collect()
+    } else {
+        project.versions
+    };
+
+    Ok(versions
+        .into_iter()
+        .filter(|version| available.get(version).copied().unwrap_or(false))
+        .collect())
+}
+
+/// Releases of a project on PyPI or a PEP 691 index
+pub struct PyPISource;
+
+impl VersionSource for PyPISource {
+    fn describe(&self, target: &FileTarget) -> String {
+        format!("PyPI project {}", target.extracted_config.tag.remote_tag)
+    }
+
+    fn list_versions(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        Ok(self
+            .list_releases(target, config)?
+            .into_iter()
+            .map(|release| release.tag)
+            .collect())
+    }
+
+    fn list_releases(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+        list_releases(
+            &normalize_name(&target.extracted_config.tag.remote_tag),
+            config,
+        )
+    }
+
+    fn compare(&self, a: &str, b: &str) -> Option<Ordering> {
+        Some(Pep440Version::parse(a)?.cmp(&Pep440Version::parse(b)?))
+    }
+
+    fn channel(&self, version: &str) -> Option<Channel> {
+        Pep440Version::parse(version).map(|version| version.channel())
+    }
+
+    fn latest(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        get_latest_tag_from_pypi(target, config)
+    }
+}
+
+/// Lists non-yanked releases from the simple index when configured, the JSON API otherwise
+fn list_releases(name: &str, config: &Config) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+    match &config.pypi.simple_url {
+        Some(simple_url) => Ok(list_versions_simple(name, simple_url)?
+            .into_iter()
+            .map(|version| Release::new(version, None))
+            .collect()),
+        None => list_versions_json(name, config),
+    }
+}
+
+pub fn get_latest_tag_from_pypi(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let name = normalize_name(&target.extracted_config.tag.remote_tag);
+    let releases = list_releases(&name, config)?;
+
+    releases
+        .into_iter()
+        .map(|release| release.tag)
+        .filter_map(|version| Pep440Version::parse(&version).map(|parsed| (parsed, version)))
+        .filter(|(parsed, _)| !parsed.is_prerelease())
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, version)| version)
+        .ok_or_else(|| format!("No stable, non-yanked releases found for {name}").into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(version: &str) -> Pep440Version {
+        Pep440Version::parse(version).expect("test versions parse")
+    }
+
+    fn assert_ascending(versions: &[&str]) {
+        for pair in versions.windows(2) {
+            assert!(parse(pair[0]) < parse(pair[1]), "{} < {}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn pre_and_post_releases_order_around_the_release() {
+        assert_ascending(&[
+            "1.0.dev1",
+            "1.0a1.dev1",
+            "1.0a1",
+            "1.0b2",
+            "1.0rc1",
+            "1.0",
+            "1.0.post1.dev1",
+            "1.0.post1",
+            "1.1.dev1",
+        ]);
+        assert_eq!(parse("1.0"), parse("1.0.0"));
+        assert_eq!(parse("1.0-alpha1"), parse("1.0a1"));
+        assert_eq!(parse("1.0c1"), parse("1.0rc1"));
+        assert_eq!(parse("1.0-1"), parse("1.0.post1"));
+    }
+
+    #[test]
+    fn epochs_outrank_release_numbers() {
+        assert!(parse("1!0.1") > parse("2.0"));
+        assert!(parse("2!1.0") > parse("1!9.0"));
+        assert_eq!(parse("0!1.0"), parse("1.0"));
+    }
+
+    #[test]
+    fn local_versions_sort_after_their_public_version() {
+        assert_ascending(&["1.0", "1.0+abc", "1.0+abd", "1.0.post1"]);
+        assert_eq!(parse("1.0+Ubuntu.1"), parse("1.0+ubuntu.1"));
+    }
+
+    #[test]
+    fn channels_follow_the_pre_release_kind() {
+        assert_eq!(parse("1.0.dev1").channel(), Channel::Dev);
+        assert_eq!(parse("1.0a1").channel(), Channel::Alpha);
+        assert_eq!(parse("1.0b1").channel(), Channel::Beta);
+        assert_eq!(parse("1.0rc1").channel(), Channel::Rc);
+        assert_eq!(parse("1.0.post1").channel(), Channel::Stable);
+        assert!(Pep440Version::parse("not a version").is_none());
+    }
+}
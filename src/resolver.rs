@@ -0,0 +1,590 @@
+use crate::{
+    constraint::Constraint,
+    cup_parser::{
+        CHANNEL_OPTION, CONSTRAINT_OPTION, FileTarget, MIN_AGE_OPTION, PRERELEASE_OPTION,
+        SKIP_OPTION, UPDATE_OPTION,
+    },
+    init::{Config, MinAge, UpdateLevel},
+    tag_family::TagFamily,
+    version::{Channel, Version},
+    version_source::{Release, VersionSource},
+};
+use jiff::Timestamp;
+use std::cmp::Ordering;
+
+/// The version picked for a target, with newer versions that were passed over and why
+pub struct Resolution {
+    pub version: String,
+    /// Newer versions left alone, e.g. `major update to 23.1.0 (update = minor)`
+    pub skipped: Vec<String>,
+    /// Newer versions published too recently, e.g. `23.1.0 (min_age = 3d), eligible from ...`
+    pub held: Vec<String>,
+    /// Problems worth reporting that did not stop the lookup
+    pub warnings: Vec<String>,
+}
+
+/// A listed release with the part of its tag that compares as a version
+struct Candidate {
+    /// Version text ordered by the source, e.g. `2.13.2` for the tag `gson-parent-2.13.2`
+    text: String,
+    /// The same text parsed leniently, for constraints and update levels
+    version: Version,
+    release: Release,
+}
+
+/// Update level from the annotation, or else from the last matching `[[rule]]`
+fn update_level(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<Option<UpdateLevel>, Box<dyn std::error::Error>> {
+    if let Some(level) = target.options.get(UPDATE_OPTION) {
+        return Ok(Some(level.parse()?));
+    }
+
+    Ok(config
+        .rules_for(
+            &target.file.full_path,
+            &target.extracted_config.tag.remote_tag,
+        )
+        .filter_map(|rule| rule.update)
+        .last())
+}
+
+/// Minimum age from the annotation, or else from the last matching `[[rule]]`, or else the
+/// global `min_age`
+fn min_age(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<Option<MinAge>, Box<dyn std::error::Error>> {
+    if let Some(age) = target.options.get(MIN_AGE_OPTION) {
+        return Ok(Some(age.parse()?));
+    }
+
+    Ok(config
+        .rules_for(
+            &target.file.full_path,
+            &target.extracted_config.tag.remote_tag,
+        )
+        .filter_map(|rule| rule.min_age)
+        .last()
+        .or(config.min_age))
+}
+
+/// Versions the annotation's `skip=` and the source's `[[ignore]]` tables rule out, each with
+/// the reason reported when it is passed over
+fn denylist(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<Vec<(Constraint, String)>, Box<dyn std::error::Error>> {
+    let mut denied = Vec::new();
+    if let Some(skip) = target.options.get(SKIP_OPTION) {
+        denied.push((Constraint::parse(skip)?, format!("skip={skip}")));
+    }
+
+    let source = &target.extracted_config.tag.remote_tag;
+    for ignore in config.ignores_for(source) {
+        let reason = match &ignore.reason {
+            Some(reason) => format!("[[ignore]] for {source}: {reason}"),
+            None => format!("[[ignore]] for {source}"),
+        };
+        for versions in &ignore.versions {
+            denied.push((Constraint::parse(versions)?, reason.clone()));
+        }
+    }
+
+    Ok(denied)
+}
+
+/// Least stable channel a target accepts, only stable releases unless the annotation opts in
+fn accepted_channel(target: &FileTarget) -> Result<Channel, Box<dyn std::error::Error>> {
+    if let Some(channel) = target.options.get(CHANNEL_OPTION) {
+        return Ok(channel.parse()?);
+    }
+
+    match target.options.get(PRERELEASE_OPTION).map(String::as_str) {
+        None | Some("false") => Ok(Channel::Stable),
+        Some("true") => Ok(Channel::Dev),
+        Some(other) => Err(format!("Invalid prerelease={other}, expected true or false").into()),
+    }
+}
+
+/// Checks that moving from `current` to `candidate` stays within an update level
+fn within_level(level: UpdateLevel, current: &Version, candidate: &Version) -> bool {
+    let fixed = match level {
+        UpdateLevel::Patch => 2,
+        UpdateLevel::Minor => 1,
+        UpdateLevel::Major => 0,
+    };
+    (0..fixed)
+        .all(|i| current.numbers.get(i).unwrap_or(&0) == candidate.numbers.get(i).unwrap_or(&0))
+}
+
+/// Names the kind of update between two versions, e.g. `minor`
+fn update_kind(current: &Version, candidate: &Version) -> &'static str {
+    if !within_level(UpdateLevel::Minor, current, candidate) {
+        "major"
+    } else if !within_level(UpdateLevel::Patch, current, candidate) {
+        "minor"
+    } else {
+        "patch"
+    }
+}
+
+/// Reports a version waiting out its minimum age, e.g. `23.1.0 (min_age = 3d), eligible from ...`
+fn held_back(tag: &str, age: MinAge, eligible: Timestamp) -> String {
+    format!(
+        "{tag} (min_age = {age}), eligible from {}",
+        eligible.strftime("%Y-%m-%d %H:%M UTC")
+    )
+}
+
+/// Reports a version adopted without a publish time to check its minimum age against
+fn no_publish_time(
+    source: &dyn VersionSource,
+    target: &FileTarget,
+    age: MinAge,
+    tag: &str,
+) -> String {
+    format!(
+        "min_age = {age} cannot be applied to {}, it records no publish time for {tag}",
+        source.describe(target)
+    )
+}
+
+/// Finds the version a target should move to from `current`, the version in the file: the
+/// source's latest version, or the highest candidate the annotation's tag family, channel,
+/// constraint, update level, minimum age and ignored versions allow
+pub fn resolve(
+    source: &dyn VersionSource,
+    target: &FileTarget,
+    config: &Config,
+    current: Option<&str>,
+) -> Result<Resolution, Box<dyn std::error::Error>> {
+    let requirement = target.options.get(CONSTRAINT_OPTION);
+    let constraint = requirement.map(|r| Constraint::parse(r)).transpose()?;
+    let level = update_level(target, config)?.filter(|level| *level != UpdateLevel::Major);
+    let channel = accepted_channel(target)?;
+    let family = TagFamily::for_target(target, config)?;
+    let min_age = min_age(target, config)?;
+    let denied = denylist(target, config)?;
+
+    // Without anything narrowing the candidates down, the source's own pick stands, e.g. an npm
+    // dist-tag; a minimum age only decides whether it is adopted yet
+    let narrowed = constraint.is_some()
+        || level.is_some()
+        || channel != Channel::Stable
+        || family.is_some()
+        || !denied.is_empty();
+    if !narrowed && min_age.is_none() {
+        return Ok(Resolution {
+            version: source.latest(target, config)?,
+            skipped: vec![],
+            held: vec![],
+            warnings: vec![],
+        });
+    }
+
+    // Publish times cost extra requests on some sources, only look them up for a minimum age
+    let (releases, latest) = match min_age {
+        Some(_) => {
+            let (releases, latest) = source.list_releases_and_latest(target, config)?;
+            (releases, latest.filter(|_| !narrowed))
+        }
+        None => {
+            let releases = source
+                .list_versions(target, config)?
+                .into_iter()
+                .map(|tag| Release::new(tag, None))
+                .collect();
+            (releases, None)
+        }
+    };
+    let latest_published = latest.as_ref().and_then(|latest| {
+        releases
+            .iter()
+            .find(|release| release.tag == *latest)
+            .and_then(|release| release.published)
+    });
+
+    let mut candidates: Vec<Candidate> = releases
+        .into_iter()
+        .filter_map(|release| {
+            let text = match &family {
+                Some(family) => family.version_of(&release.tag)?,
+                None => &release.tag,
+            };
+            let text = source.version_of(target, text).to_string();
+            let version = Version::parse(&text)?;
+            (source.channel(&text)? >= channel).then_some(Candidate {
+                text,
+                version,
+                release,
+            })
+        })
+        .filter(|candidate| {
+            constraint
+                .as_ref()
+                .is_none_or(|c| c.matches(&candidate.version))
+        })
+        .collect();
+    candidates.sort_by(|a, b| source.compare(&a.text, &b.text).unwrap_or(Ordering::Equal));
+    let is_newer = |a: &str, b: &str| source.compare(a, b) == Some(Ordering::Greater);
+
+    // Ignored versions are passed over for the next best candidate
+    let ignore_reason = |candidate: &Candidate| {
+        denied
+            .iter()
+            .find(|(versions, _)| versions.matches(&candidate.version))
+            .map(|(_, reason)| reason.as_str())
+    };
+    let (ignored, candidates): (Vec<Candidate>, Vec<Candidate>) = candidates
+        .into_iter()
+        .partition(|candidate| ignore_reason(candidate).is_some());
+
+    // Versions published less than min_age ago wait until they are old enough; versions
+    // without a known publish time are not held back
+    let now = Timestamp::now();
+    let eligible_from = |published: Option<Timestamp>| {
+        min_age
+            .zip(published)
+            .and_then(|(age, published)| published.checked_add(age.0).ok())
+            .filter(|eligible| *eligible > now)
+    };
+
+    if let (Some(latest), Some(age)) = (latest, min_age) {
+        match eligible_from(latest_published) {
+            None => {
+                let warnings = match latest_published {
+                    Some(_) => vec![],
+                    None => vec![no_publish_time(source, target, age, &latest)],
+                };
+                return Ok(Resolution {
+                    version: latest,
+                    skipped: vec![],
+                    held: vec![],
+                    warnings,
+                });
+            }
+            // The source picked the highest stable release, an older one may be eligible
+            Some(_) if candidates.last().is_some_and(|c| c.release.tag == latest) => {}
+            // Anything else the source picked, such as a dist-tag, waits as a whole
+            Some(eligible) => {
+                let version = current.map(str::to_string).ok_or_else(|| {
+                    format!("{latest} was published less than min_age = {age} ago")
+                })?;
+                return Ok(Resolution {
+                    version,
+                    skipped: vec![],
+                    held: vec![held_back(&latest, age, eligible)],
+                    warnings: vec![],
+                });
+            }
+        }
+    }
+
+    let (fresh, candidates): (Vec<Candidate>, Vec<Candidate>) = candidates
+        .into_iter()
+        .partition(|candidate| eligible_from(candidate.release.published).is_some());
+
+    // Keep the value in the shape the file already uses, prefixed or not
+    let written = |tag: &str| match &family {
+        Some(family) => family.shape(tag, current).to_string(),
+        None => tag.to_string(),
+    };
+    let current_text = current.map(|raw| {
+        family
+            .as_ref()
+            .and_then(|family| family.version_of(raw))
+            .unwrap_or(raw)
+    });
+
+    let (picked, mut skipped) = match level {
+        None => (candidates.last(), vec![]),
+        Some(level) => {
+            let current_raw =
+                current.ok_or("No current version in the line to apply the update level to")?;
+            let current_text = current_text.unwrap_or(current_raw);
+            let current = Version::parse(current_text)
+                .ok_or_else(|| format!("Cannot apply the update level to {current_raw}"))?;
+
+            let allowed = candidates
+                .iter()
+                .rfind(|candidate| within_level(level, &current, &candidate.version));
+
+            // Report the newest version of each larger kind of update the level kept out
+            let mut skipped: Vec<(&'static str, &String)> = vec![];
+            for candidate in candidates.iter().rev() {
+                let newer = is_newer(&candidate.text, current_text)
+                    && allowed.is_none_or(|picked| is_newer(&candidate.text, &picked.text));
+                let kind = update_kind(&current, &candidate.version);
+                if newer
+                    && !within_level(level, &current, &candidate.version)
+                    && skipped.iter().all(|(seen, _)| *seen != kind)
+                {
+                    skipped.push((kind, &candidate.release.tag));
+                }
+            }
+            let skipped = skipped
+                .into_iter()
+                .rev()
+                .map(|(kind, tag)| format!("{kind} update to {tag} (update = {level})"))
+                .collect();
+
+            (allowed, skipped)
+        }
+    };
+
+    // Report held back and ignored versions newer than both the picked and the current version
+    let above_floor = |candidate: &&Candidate| {
+        picked.is_none_or(|picked| is_newer(&candidate.text, &picked.text))
+            && current_text.is_none_or(|current| is_newer(&candidate.text, current))
+    };
+    let held = match min_age {
+        Some(age) => fresh
+            .iter()
+            .filter(above_floor)
+            .filter_map(|candidate| {
+                let eligible = eligible_from(candidate.release.published)?;
+                Some(held_back(&candidate.release.tag, age, eligible))
+            })
+            .collect(),
+        None => vec![],
+    };
+    skipped.extend(ignored.iter().filter(above_floor).filter_map(|candidate| {
+        let reason = ignore_reason(candidate)?;
+        Some(format!("{} is ignored ({reason})", candidate.release.tag))
+    }));
+
+    // Versions without a publish time are adopted right away, say so rather than stay silent
+    let warnings = match (min_age, picked) {
+        (Some(age), Some(candidate)) if candidate.release.published.is_none() => {
+            vec![no_publish_time(source, target, age, &candidate.release.tag)]
+        }
+        _ => vec![],
+    };
+
+    let version = match (picked, current) {
+        (Some(candidate), _) => written(&candidate.release.tag),
+        // Nothing is eligible yet, so the file keeps its version
+        (None, Some(current)) if level.is_some() || !fresh.is_empty() || !ignored.is_empty() => {
+            current.to_string()
+        }
+        (None, _) => {
+            return Err(match requirement {
+                Some(requirement) => format!("No {channel} version satisfies {requirement}"),
+                None => format!("No {channel} versions found"),
+            }
+            .into());
+        }
+    };
+
+    Ok(Resolution {
+        version,
+        skipped,
+        held,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        file_finder::FileInfo,
+        init::{Remote, Tag, Target},
+    };
+    use jiff::SignedDuration;
+    use std::path::PathBuf;
+
+    /// Serves a fixed list of releases from memory
+    struct FakeSource {
+        releases: Vec<Release>,
+    }
+
+    impl FakeSource {
+        fn new(tags: &[&str]) -> Self {
+            FakeSource {
+                releases: tags
+                    .iter()
+                    .map(|tag| Release::new(tag.to_string(), None))
+                    .collect(),
+            }
+        }
+
+        /// Releases published the given number of hours ago
+        fn timed(tags: &[(&str, i64)]) -> Self {
+            let now = Timestamp::now();
+            FakeSource {
+                releases: tags
+                    .iter()
+                    .map(|(tag, hours)| Release {
+                        tag: tag.to_string(),
+                        published: now.checked_sub(SignedDuration::from_hours(*hours)).ok(),
+                    })
+                    .collect(),
+            }
+        }
+    }
+
+    impl VersionSource for FakeSource {
+        fn describe(&self, _target: &FileTarget) -> String {
+            "fake source".to_string()
+        }
+
+        fn list_versions(
+            &self,
+            _target: &FileTarget,
+            _config: &Config,
+        ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+            Ok(self.releases.iter().map(|r| r.tag.clone()).collect())
+        }
+
+        fn list_releases(
+            &self,
+            _target: &FileTarget,
+            _config: &Config,
+        ) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+            Ok(self.releases.clone())
+        }
+    }
+
+    fn target(options: &[(&str, &str)]) -> FileTarget {
+        FileTarget {
+            file: FileInfo {
+                full_path: PathBuf::from("./versions.toml"),
+                filename: "versions.toml".to_string(),
+                file_type: "toml".to_string(),
+                content: String::new(),
+            },
+            row: 0,
+            extracted_config: Target {
+                name: "./versions.toml:1".to_string(),
+                tag: Tag {
+                    remote_tag: "owner/repo".to_string(),
+                    remote_type: Remote::GitHub,
+                },
+            },
+            options: options
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    fn resolve_with(
+        source: &FakeSource,
+        options: &[(&str, &str)],
+        current: Option<&str>,
+    ) -> Resolution {
+        resolve(source, &target(options), &Config::default(), current).expect("resolves")
+    }
+
+    #[test]
+    fn constraint_picks_the_highest_match() {
+        let source = FakeSource::new(&["1.9.0", "1.9.5", "2.0.0-rc.1", "2.0.0"]);
+        let resolution = resolve_with(&source, &[(CONSTRAINT_OPTION, ">=1.9 <2")], None);
+        assert_eq!(resolution.version, "1.9.5");
+
+        let error = resolve(
+            &source,
+            &target(&[(CONSTRAINT_OPTION, "^3")]),
+            &Config::default(),
+            None,
+        );
+        assert!(error.is_err());
+    }
+
+    #[test]
+    fn update_level_reports_the_skipped_updates() {
+        let source = FakeSource::new(&["1.2.0", "1.2.5", "1.3.0", "1.4.0", "2.0.0"]);
+
+        let resolution = resolve_with(&source, &[(UPDATE_OPTION, "patch")], Some("1.2.0"));
+        assert_eq!(resolution.version, "1.2.5");
+        assert_eq!(
+            resolution.skipped,
+            [
+                "minor update to 1.4.0 (update = patch)",
+                "major update to 2.0.0 (update = patch)",
+            ]
+        );
+
+        let resolution = resolve_with(&source, &[(UPDATE_OPTION, "minor")], Some("1.2.0"));
+        assert_eq!(resolution.version, "1.4.0");
+        assert_eq!(
+            resolution.skipped,
+            ["major update to 2.0.0 (update = minor)"]
+        );
+    }
+
+    #[test]
+    fn denied_versions_are_passed_over() {
+        let source = FakeSource::new(&["1.2.0", "1.3.0", "1.4.0"]);
+        let resolution = resolve_with(&source, &[(SKIP_OPTION, "1.4.0")], Some("1.2.0"));
+        assert_eq!(resolution.version, "1.3.0");
+        assert_eq!(resolution.skipped, ["1.4.0 is ignored (skip=1.4.0)"]);
+
+        // Nothing left but the current version, which stays
+        let resolution = resolve_with(&source, &[(SKIP_OPTION, ">1.2.0")], Some("1.2.0"));
+        assert_eq!(resolution.version, "1.2.0");
+    }
+
+    #[test]
+    fn min_age_holds_back_recent_releases() {
+        let source = FakeSource::timed(&[("1.0.0", 240), ("1.1.0", 48), ("1.2.0", 1)]);
+
+        let resolution = resolve_with(&source, &[(MIN_AGE_OPTION, "3d")], Some("1.0.0"));
+        assert_eq!(resolution.version, "1.0.0");
+        assert_eq!(resolution.held.len(), 2);
+        assert!(resolution.held[0].starts_with("1.1.0 (min_age = 3d), eligible from "));
+        assert!(resolution.warnings.is_empty());
+
+        let resolution = resolve_with(&source, &[(MIN_AGE_OPTION, "1d")], Some("1.0.0"));
+        assert_eq!(resolution.version, "1.1.0");
+        assert_eq!(resolution.held.len(), 1);
+        assert!(resolution.held[0].starts_with("1.2.0 (min_age = 1d)"));
+    }
+
+    #[test]
+    fn min_age_warns_without_publish_times() {
+        let source = FakeSource::new(&["1.0.0", "1.1.0"]);
+        let resolution = resolve_with(&source, &[(MIN_AGE_OPTION, "3d")], Some("1.0.0"));
+        assert_eq!(resolution.version, "1.1.0");
+        assert!(resolution.held.is_empty());
+        assert_eq!(
+            resolution.warnings,
+            ["min_age = 3d cannot be applied to fake source, it records no publish time for 1.1.0"]
+        );
+
+        let resolution = resolve_with(
+            &source,
+            &[(MIN_AGE_OPTION, "3d"), (UPDATE_OPTION, "patch")],
+            Some("1.0.0"),
+        );
+        assert_eq!(resolution.version, "1.0.0");
+        assert_eq!(resolution.warnings.len(), 1);
+    }
+
+    #[test]
+    fn channel_admits_pre_releases_down_to_it() {
+        let source = FakeSource::new(&["1.0.0", "1.1.0-alpha.1", "1.1.0-beta.2", "1.1.0-rc.1"]);
+        assert_eq!(resolve_with(&source, &[], None).version, "1.0.0");
+        assert_eq!(
+            resolve_with(&source, &[(CHANNEL_OPTION, "rc")], None).version,
+            "1.1.0-rc.1"
+        );
+
+        let source = FakeSource::new(&["1.0.0", "1.1.0-alpha.1", "1.1.0-beta.2"]);
+        assert_eq!(
+            resolve_with(&source, &[(CHANNEL_OPTION, "rc")], None).version,
+            "1.0.0"
+        );
+        assert_eq!(
+            resolve_with(&source, &[(CHANNEL_OPTION, "beta")], None).version,
+            "1.1.0-beta.2"
+        );
+        assert_eq!(
+            resolve_with(&source, &[(PRERELEASE_OPTION, "true")], None).version,
+            "1.1.0-beta.2"
+        );
+    }
+}
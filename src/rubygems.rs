@@ -0,0 +1,87 @@
+use serde::Deserialize;
+
+use crate::{
+    cup_parser::FileTarget,
+    http,
+    init::Config,
+    version,
+    version_source::{Release, VersionSource},
+};
+
+#[derive(Debug, Deserialize)]
+struct GemVersion {
+    number: String,
+    #[serde(default)]
+    prerelease: bool,
+    #[serde(default)]
+    created_at: Option<String>,
+}
+
+/// Versions of a gem on RubyGems.org or a compatible server
+pub struct RubyGemsSource;
+
+impl VersionSource for RubyGemsSource {
+    fn describe(&self, target: &FileTarget) -> String {
+        format!("gem {}", target.extracted_config.tag.remote_tag)
+    }
+
+    fn list_versions(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        Ok(list_gem_versions(target, config)?
+            .into_iter()
+            .map(|gem_version| gem_version.number)
+            .collect())
+    }
+
+    fn list_releases(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+        Ok(list_gem_versions(target, config)?
+            .into_iter()
+            .map(|gem_version| Release::new(gem_version.number, gem_version.created_at.as_deref()))
+            .collect())
+    }
+
+    fn latest(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        get_latest_tag_from_rubygems(target, config)
+    }
+}
+
+fn list_gem_versions(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<Vec<GemVersion>, Box<dyn std::error::Error>> {
+    let gem = &target.extracted_config.tag.remote_tag;
+    let url = format!(
+        "{}/api/v1/versions/{gem}.json",
+        config.rubygems.url.trim_end_matches('/')
+    );
+
+    http::get_json::<Vec<GemVersion>>(&url, &[])?
+        .ok_or_else(|| format!("Gem {gem} not found at {url}").into())
+}
+
+pub fn get_latest_tag_from_rubygems(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let gem = &target.extracted_config.tag.remote_tag;
+    let numbers: Vec<String> = list_gem_versions(target, config)?
+        .into_iter()
+        .filter(|gem_version| !gem_version.prerelease)
+        .map(|gem_version| gem_version.number)
+        .collect();
+
+    version::highest_stable(&numbers)
+        .map(str::to_string)
+        .ok_or_else(|| format!("No stable versions found for gem {gem}").into())
+}
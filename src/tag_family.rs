@@ -0,0 +1,64 @@
+use regex::Regex;
+
+use crate::{
+    cup_parser::{FileTarget, PREFIX_OPTION, TAG_OPTION},
+    init::Config,
+};
+
+/// Tags of one component in a repository publishing several, e.g. `gson-parent-2.13.2`
+pub struct TagFamily {
+    pattern: Regex,
+}
+
+impl TagFamily {
+    /// Family from the annotation's `tag=` or `prefix=`, or else from the last matching `[[rule]]`
+    pub fn for_target(
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Option<TagFamily>, Box<dyn std::error::Error>> {
+        let pattern = if let Some(tag) = target.options.get(TAG_OPTION) {
+            tag.clone()
+        } else if let Some(prefix) = target.options.get(PREFIX_OPTION) {
+            format!("{}(.*)", regex::escape(prefix))
+        } else {
+            let rule_tag = config
+                .rules_for(
+                    &target.file.full_path,
+                    &target.extracted_config.tag.remote_tag,
+                )
+                .filter_map(|rule| rule.tag.clone())
+                .last();
+            match rule_tag {
+                Some(tag) => tag,
+                None => return Ok(None),
+            }
+        };
+
+        // The pattern has to match the whole tag so `parent-(.*)` does not pick up `gson-parent-2.13.2`
+        let regex = Regex::new(&format!("^(?:{pattern})$"))
+            .map_err(|e| format!("Invalid tag pattern {pattern}: {e}"))?;
+        if regex.captures_len() < 2 {
+            return Err(
+                format!("Tag pattern {pattern} needs a capture group around the version").into(),
+            );
+        }
+
+        Ok(Some(TagFamily { pattern: regex }))
+    }
+
+    /// Version captured from a tag of the family, `None` for tags of other families
+    pub fn version_of<'a>(&self, tag: &'a str) -> Option<&'a str> {
+        self.pattern.captures(tag)?.get(1).map(|m| m.as_str())
+    }
+
+    /// Value to write for a picked tag: the whole tag when the current value is written the same
+    /// way (`gson-parent-2.13.2`), otherwise only its version (`2.13.2`)
+    pub fn shape<'a>(&self, tag: &'a str, current: Option<&str>) -> &'a str {
+        match current {
+            Some(current) if self.version_of(current).is_none() => {
+                self.version_of(tag).unwrap_or(tag)
+            }
+            _ => tag,
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::{
+    cup_parser::{FileTarget, TEMPLATE_OPTION},
+    init::Config,
+};
+
+// Leading text (e.g. "v", "gson-parent-"), dotted numeric core, then any suffix
+static SHAPE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^([^0-9]*?)([0-9]+(?:\.[0-9]+)*)(.*)$").expect("Failed to compile shape regex")
+});
+
+// Placeholders such as {major} or {version}
+static PLACEHOLDER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{([a-z]*)\}").expect("Failed to compile placeholder regex"));
+
+const PLACEHOLDERS: [&str; 5] = ["major", "minor", "patch", "version", "tag"];
+
+/// How a picked version is written into the file
+pub enum OutputTemplate {
+    /// The version as published, with a `v` prefix only when the current value has one
+    Default,
+    /// Prefix and number of components of the current value, e.g. `v1.2` stays `v1.3`
+    Same,
+    /// Placeholders filled from the version, e.g. `v{major}.{minor}.{patch}` or `{version}-alpine`
+    Pattern(String),
+}
+
+/// Splits a value into its prefix, dotted numbers and suffix, e.g. `v1.2.3-rc.1` into `v`,
+/// `1.2.3` and `-rc.1`
+fn split_shape(value: &str) -> Option<(&str, &str, &str)> {
+    let caps = SHAPE_RE.captures(value)?;
+    Some((
+        caps.get(1)?.as_str(),
+        caps.get(2)?.as_str(),
+        caps.get(3)?.as_str(),
+    ))
+}
+
+/// Strips a `v` or `V` directly in front of a number, so `v1.0-dev` becomes `1.0-dev` but
+/// `version-1` is kept
+fn strip_v(value: &str) -> &str {
+    match value.strip_prefix(['v', 'V']) {
+        Some(rest) if rest.starts_with(|c: char| c.is_ascii_digit()) => rest,
+        _ => value,
+    }
+}
+
+impl OutputTemplate {
+    /// Template from the annotation's `template=`, or else from the last matching `[[rule]]`
+    pub fn for_target(target: &FileTarget, config: &Config) -> Result<OutputTemplate, String> {
+        let template = target.options.get(TEMPLATE_OPTION).cloned().or_else(|| {
+            config
+                .rules_for(
+                    &target.file.full_path,
+                    &target.extracted_config.tag.remote_tag,
+                )
+                .filter_map(|rule| rule.template.clone())
+                .last()
+        });
+
+        match template.as_deref() {
+            None => Ok(OutputTemplate::Default),
+            Some("same") => Ok(OutputTemplate::Same),
+            Some(pattern) => {
+                if let Some(unknown) = PLACEHOLDER_RE
+                    .captures_iter(pattern)
+                    .map(|caps| caps[1].to_string())
+                    .find(|name| !PLACEHOLDERS.contains(&name.as_str()))
+                {
+                    return Err(format!(
+                        "Unknown placeholder {{{unknown}}} in template {pattern}, expected {{major}}, {{minor}}, {{patch}}, {{version}} or {{tag}}"
+                    ));
+                }
+                Ok(OutputTemplate::Pattern(pattern.to_string()))
+            }
+        }
+    }
+
+    /// Formats a picked tag for the file, given the value currently written there
+    pub fn render(&self, tag: &str, current: Option<&str>) -> Result<String, String> {
+        match self {
+            OutputTemplate::Default => {
+                let bare = strip_v(tag);
+                match current {
+                    // Only a version number takes the `v` back, `other-9.9.9` is written as is
+                    Some(current)
+                        if strip_v(current) != current
+                            && bare.starts_with(|c: char| c.is_ascii_digit()) =>
+                    {
+                        Ok(format!("{}{bare}", &current[..1]))
+                    }
+                    _ => Ok(bare.to_string()),
+                }
+            }
+            OutputTemplate::Same => {
+                let Some((prefix, numbers, _)) = current.and_then(split_shape) else {
+                    return Ok(strip_v(tag).to_string());
+                };
+                let (_, new_numbers, new_suffix) =
+                    split_shape(tag).ok_or_else(|| format!("Cannot apply template to {tag}"))?;
+
+                let count = numbers.split('.').count();
+                let new_numbers: Vec<&str> = new_numbers.split('.').take(count).collect();
+                Ok(format!("{prefix}{}{new_suffix}", new_numbers.join(".")))
+            }
+            OutputTemplate::Pattern(pattern) => {
+                let (_, numbers, suffix) = split_shape(tag)
+                    .ok_or_else(|| format!("Cannot apply template {pattern} to {tag}"))?;
+                let numbers: Vec<&str> = numbers.split('.').collect();
+
+                Ok(PLACEHOLDER_RE
+                    .replace_all(pattern, |caps: &regex::Captures| match &caps[1] {
+                        "major" => numbers[0].to_string(),
+                        "minor" => numbers.get(1).unwrap_or(&"0").to_string(),
+                        "patch" => numbers.get(2).unwrap_or(&"0").to_string(),
+                        "version" => format!("{}{suffix}", numbers.join(".")),
+                        _ => tag.to_string(),
+                    })
+                    .to_string())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &OutputTemplate, tag: &str, current: &str) -> String {
+        template
+            .render(tag, Some(current))
+            .expect("test templates render")
+    }
+
+    #[test]
+    fn default_keeps_the_v_prefix_of_the_current_value() {
+        let template = OutputTemplate::Default;
+        assert_eq!(render(&template, "v1.3.0", "1.2.0"), "1.3.0");
+        assert_eq!(render(&template, "1.3.0", "v1.2.0"), "v1.3.0");
+        assert_eq!(render(&template, "v1.3.0", "V1.2.0"), "V1.3.0");
+        assert_eq!(render(&template, "1.3.0-rc.1", "v1.2.0"), "v1.3.0-rc.1");
+        assert_eq!(template.render("v1.3.0", None).as_deref(), Ok("1.3.0"));
+    }
+
+    #[test]
+    fn default_writes_tags_without_a_leading_number_as_is() {
+        let template = OutputTemplate::Default;
+        assert_eq!(render(&template, "other-9.9.9", "v1.2.0"), "other-9.9.9");
+        assert_eq!(render(&template, "release-2.0", "v1.2.0"), "release-2.0");
+    }
+
+    #[test]
+    fn same_keeps_prefix_and_component_count() {
+        let template = OutputTemplate::Same;
+        assert_eq!(render(&template, "1.3.7", "v1.2"), "v1.3");
+        assert_eq!(render(&template, "v2.0.1", "1.2.3"), "2.0.1");
+        assert_eq!(
+            render(&template, "gson-parent-2.13.2", "gson-parent-2.10.0"),
+            "gson-parent-2.13.2"
+        );
+        assert_eq!(render(&template, "2.1.0-rc.1", "1.2.0"), "2.1.0-rc.1");
+        assert_eq!(template.render("v1.3.0", None).as_deref(), Ok("1.3.0"));
+    }
+
+    #[test]
+    fn pattern_fills_placeholders() {
+        let template = OutputTemplate::Pattern("v{major}.{minor}.{patch}".to_string());
+        assert_eq!(render(&template, "1.3", "1.2.0"), "v1.3.0");
+
+        let template = OutputTemplate::Pattern("{version}-alpine ({tag})".to_string());
+        assert_eq!(
+            render(&template, "v2.1.0-rc.1", "1.0.0"),
+            "2.1.0-rc.1-alpine (v2.1.0-rc.1)"
+        );
+
+        let template = OutputTemplate::Pattern("{major}".to_string());
+        assert!(template.render("latest", None).is_err());
+    }
+}
@@ -0,0 +1,178 @@
+use serde::Deserialize;
+
+use crate::{cup_parser::FileTarget, http, init::Config, version, version_source::VersionSource};
+
+#[derive(Debug, Deserialize)]
+struct Discovery {
+    #[serde(rename = "providers.v1")]
+    providers: Option<String>,
+    #[serde(rename = "modules.v1")]
+    modules: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VersionEntry {
+    version: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProviderVersions {
+    versions: Vec<VersionEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModuleVersions {
+    modules: Vec<ProviderVersions>,
+}
+
+/// A provider (`ns/type`) or module (`ns/name/provider`) address, optionally prefixed by a host
+struct RegistryAddress<'a> {
+    /// Scheme and host of the registry, e.g. `https://registry.terraform.io`
+    base: String,
+    host: String,
+    /// Address without the host, e.g. `hashicorp/aws`
+    path: Vec<&'a str>,
+}
+
+impl<'a> RegistryAddress<'a> {
+    fn parse(location: &'a str, config: &Config) -> Option<RegistryAddress<'a>> {
+        let parts: Vec<&str> = location.split('/').filter(|p| !p.is_empty()).collect();
+
+        let configured = config.terraform.url.trim_end_matches('/');
+        let (base, path) = match parts.first() {
+            // The configured registry keeps its scheme, e.g. http:// for a local mirror
+            Some(first) if parts.len() > 2 && *first == url_host(configured) => {
+                (configured.to_string(), parts[1..].to_vec())
+            }
+            // registry.example.com/ns/type, the host names a private registry
+            Some(first) if parts.len() > 2 && (first.contains('.') || first.contains(':')) => {
+                (format!("https://{first}"), parts[1..].to_vec())
+            }
+            _ => (configured.to_string(), parts),
+        };
+
+        if !(2..=3).contains(&path.len()) {
+            return None;
+        }
+
+        let host = url_host(&base).to_string();
+
+        Some(RegistryAddress { base, host, path })
+    }
+
+    fn is_module(&self) -> bool {
+        self.path.len() == 3
+    }
+}
+
+/// Returns the host, with its port if any, of a URL such as `https://tf.example.com/registry`
+fn url_host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split('/').next().unwrap_or(rest)
+}
+
+/// Reads the token for a host from `TF_TOKEN_<host>`, following Terraform's variable naming
+fn host_token(host: &str) -> Option<String> {
+    let host = host.split(':').next().unwrap_or(host);
+    let name = format!("TF_TOKEN_{}", host.replace('-', "__").replace('.', "_"));
+    std::env::var(name).ok().filter(|token| !token.is_empty())
+}
+
+/// Resolves a discovered service path against the registry base URL
+fn service_url(base: &str, service: &str) -> String {
+    if service.starts_with("http://") || service.starts_with("https://") {
+        service.trim_end_matches('/').to_string()
+    } else {
+        format!("{base}/{}", service.trim_matches('/'))
+    }
+}
+
+/// Versions of a provider or module in a Terraform registry
+pub struct TerraformSource;
+
+impl VersionSource for TerraformSource {
+    fn describe(&self, target: &FileTarget) -> String {
+        format!("Terraform {}", target.extracted_config.tag.remote_tag)
+    }
+
+    fn list_versions(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        list_registry_versions(target, config)
+    }
+
+    fn latest(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        get_latest_tag_from_terraform(target, config)
+    }
+}
+
+/// Lists every version of a provider or module, found through service discovery
+fn list_registry_versions(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let location = &target.extracted_config.tag.remote_tag;
+    let address = RegistryAddress::parse(location, config).ok_or_else(|| {
+        format!(
+            "Expected a provider (ns/type) or module (ns/name/provider) address, got {location}"
+        )
+    })?;
+
+    let headers: Vec<(&str, String)> = host_token(&address.host)
+        .map(|token| vec![("Authorization", format!("Bearer {token}"))])
+        .unwrap_or_default();
+
+    // Service discovery tells where the provider and module APIs live
+    let discovery_url = format!("{}/.well-known/terraform.json", address.base);
+    let discovery = http::get_json::<Discovery>(&discovery_url, &headers)?
+        .ok_or_else(|| format!("{} is not a Terraform registry", address.base))?;
+
+    let versions = if address.is_module() {
+        let service = discovery
+            .modules
+            .ok_or_else(|| format!("{} does not serve modules", address.base))?;
+        let url = format!(
+            "{}/{}/versions",
+            service_url(&address.base, &service),
+            address.path.join("/")
+        );
+        http::get_json::<ModuleVersions>(&url, &headers)?
+            .ok_or_else(|| format!("Module {location} not found"))?
+            .modules
+            .into_iter()
+            .flat_map(|module| module.versions)
+            .collect::<Vec<_>>()
+    } else {
+        let service = discovery
+            .providers
+            .ok_or_else(|| format!("{} does not serve providers", address.base))?;
+        let url = format!(
+            "{}/{}/versions",
+            service_url(&address.base, &service),
+            address.path.join("/")
+        );
+        http::get_json::<ProviderVersions>(&url, &headers)?
+            .ok_or_else(|| format!("Provider {location} not found"))?
+            .versions
+    };
+
+    Ok(versions.into_iter().map(|entry| entry.version).collect())
+}
+
+pub fn get_latest_tag_from_terraform(
+    target: &FileTarget,
+    config: &Config,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let location = &target.extracted_config.tag.remote_tag;
+    let versions = list_registry_versions(target, config)?;
+
+    version::highest_stable(&versions)
+        .map(str::to_string)
+        .ok_or_else(|| format!("No stable versions found for {location}").into())
+}
@@ -1,15 +1,26 @@
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
-use serde::Deserialize;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 
 use crate::{
     cup_parser::{FileTarget, find_cup_targets},
     file_finder::{self},
     init::Config,
-    version_extractor::{clean_tag, try_replace_version_in_line},
+    resolver,
+    template::OutputTemplate,
+    version_extractor::{extract_version_from_line, try_replace_version_in_line},
+    version_source::SourceRegistry,
 };
 
-pub fn update(config: Config) {
+/// Flags changing how found versions are applied
+#[derive(Debug, Default)]
+pub struct UpdateOptions {
+    /// write a version even when it is older than the one in the file
+    pub allow_downgrade: bool,
+}
+
+pub fn update(config: Config, options: UpdateOptions) {
+    let sources = SourceRegistry::with_plugins(&config);
     let targets = do_cups(&config);
 
     // Group targets by file path to handle multiple targets per file
@@ -21,11 +32,16 @@ pub fn update(config: Config) {
 
     // Process each file with all its targets
     targets_by_file.par_iter().for_each(|(_, file_targets)| {
-        process_file_targets(file_targets);
+        process_file_targets(file_targets, &config, &sources, &options);
     });
 }
 
-fn process_file_targets(targets: &[FileTarget]) {
+fn process_file_targets(
+    targets: &[FileTarget],
+    config: &Config,
+    sources: &SourceRegistry,
+    options: &UpdateOptions,
+) {
     if targets.is_empty() {
         return;
     }
@@ -46,19 +62,14 @@ fn process_file_targets(targets: &[FileTarget]) {
 
     // Process each target and update the corresponding line
     for target in targets {
-        let latest_tag = match get_latest_tag_from_github(target) {
-            Ok(tag) => tag,
+        let source = match sources.get(&target.extracted_config.tag.remote_type) {
+            Ok(source) => source,
             Err(e) => {
-                eprintln!(
-                    "Error getting latest tag for {}: {}",
-                    target.extracted_config.tag.remote_tag, e
-                );
+                eprintln!("{e}");
                 continue;
             }
         };
 
-        let clean_version = clean_tag(latest_tag);
-
         if target.row as usize >= lines.len() {
             eprintln!(
                 "Row index {} out of bounds for file {}",
@@ -69,6 +80,87 @@ fn process_file_targets(targets: &[FileTarget]) {
         }
 
         let line = &lines[target.row as usize];
+        let current = extract_version_from_line(line);
+
+        let resolution = match resolver::resolve(source, target, config, current.as_deref()) {
+            Ok(resolution) => resolution,
+            Err(e) => {
+                eprintln!(
+                    "Error getting latest tag from {}: {}",
+                    source.describe(target),
+                    e
+                );
+                continue;
+            }
+        };
+
+        for skipped in &resolution.skipped {
+            println!(
+                "Skipped {}:{} {}",
+                file_path.display(),
+                target.row + 1,
+                skipped
+            );
+        }
+
+        for held in &resolution.held {
+            println!(
+                "Held back {}:{} {}",
+                file_path.display(),
+                target.row + 1,
+                held
+            );
+        }
+
+        for warning in &resolution.warnings {
+            eprintln!(
+                "Warning {}:{} {}",
+                file_path.display(),
+                target.row + 1,
+                warning
+            );
+        }
+
+        let clean_version = match OutputTemplate::for_target(target, config)
+            .and_then(|template| template.render(&resolution.version, current.as_deref()))
+        {
+            Ok(version) => version,
+            Err(e) => {
+                eprintln!(
+                    "Error formatting version for {}:{}: {}",
+                    file_path.display(),
+                    target.row + 1,
+                    e
+                );
+                continue;
+            }
+        };
+
+        // Only move forward unless downgrades are explicitly allowed
+        if let Some(current) = current {
+            match source.compare(&clean_version, &current) {
+                Some(Ordering::Equal) => {
+                    println!(
+                        "Already up to date {}:{} at version {}",
+                        file_path.display(),
+                        target.row + 1,
+                        current
+                    );
+                    continue;
+                }
+                Some(Ordering::Less) if !options.allow_downgrade => {
+                    println!(
+                        "Skipped {}:{}, latest version {} is older than {} (use --allow-downgrade to apply it)",
+                        file_path.display(),
+                        target.row + 1,
+                        clean_version,
+                        current
+                    );
+                    continue;
+                }
+                _ => {}
+            }
+        }
 
         if let Some(updated_line) = try_replace_version_in_line(line, &clean_version) {
             lines[target.row as usize] = updated_line;
@@ -115,36 +207,3 @@ fn do_cups(config: &Config) -> Vec<FileTarget> {
 
     find_cup_targets(&files, config)
 }
-
-#[derive(Debug, Deserialize)]
-struct LatestTag {
-    #[serde(alias = "tagName")]
-    tag_name: String,
-}
-
-fn get_latest_tag_from_github(target: &FileTarget) -> Result<String, Box<dyn std::error::Error>> {
-    let sh = xshell::Shell::new()?;
-    let owner_and_repo = target.extracted_config.tag.remote_tag.clone();
-
-    // First try to get the latest release
-    if let Ok(res) =
-        xshell::cmd!(sh, "gh release view --repo {owner_and_repo} --json tagName").read()
-    {
-        if let Ok(tag_name) = serde_json::from_str::<LatestTag>(&res) {
-            return Ok(tag_name.tag_name);
-        }
-    }
-
-    // If release fails, try to get the latest tag
-    println!("release not found");
-    let res = xshell::cmd!(sh, "gh api repos/{owner_and_repo}/tags --jq '.[0].name'")
-        .read()
-        .map_err(|e| format!("Failed to get tags for {owner_and_repo}: {e}"))?;
-
-    let tag_name = res.trim().to_string();
-    if tag_name.is_empty() {
-        return Err(format!("No tags found for repository {owner_and_repo}").into());
-    }
-
-    Ok(tag_name)
-}
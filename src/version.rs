@@ -0,0 +1,205 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+// Leading text (e.g. "v", "release-"), dotted numeric core, then any suffix
+static VERSION_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[^0-9]*?([0-9]+(?:\.[0-9]+)*)(.*)$").expect("Failed to compile version regex")
+});
+
+/// A version parsed leniently from a tag such as `v1.2.3`, `release-1.2` or `1.2.3-rc.1`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version {
+    /// Dotted numeric components, e.g. `[1, 2, 3]`
+    pub numbers: Vec<u64>,
+    /// Pre-release suffix without its leading separator, e.g. `rc.1`
+    pub pre: Option<String>,
+}
+
+impl Version {
+    /// Parses a version out of a tag, returning `None` when it contains no number
+    pub fn parse(tag: &str) -> Option<Version> {
+        let caps = VERSION_RE.captures(tag.trim())?;
+        let numbers = caps[1]
+            .split('.')
+            .map(|n| n.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()?;
+
+        // Build metadata does not take part in ordering
+        let suffix = caps[2].split('+').next().unwrap_or("");
+        let pre = suffix.trim_start_matches(['-', '.', '_']);
+        let pre = (!pre.is_empty()).then(|| pre.to_string());
+
+        Some(Version { numbers, pre })
+    }
+
+    /// Classifies the suffix by its leading label: `rc.1` and `RC2` are release candidates,
+    /// `M1` a milestone, `b3` a beta; `Final`, `RELEASE` and `GA` are stable
+    pub fn channel(&self) -> Channel {
+        let Some(pre) = &self.pre else {
+            return Channel::Stable;
+        };
+        let label: String = pre
+            .chars()
+            .take_while(char::is_ascii_alphabetic)
+            .collect::<String>()
+            .to_lowercase();
+
+        match label.as_str() {
+            "final" | "release" | "ga" => Channel::Stable,
+            "rc" | "cr" | "pre" => Channel::Rc,
+            "m" | "milestone" => Channel::Milestone,
+            "beta" | "b" | "preview" => Channel::Beta,
+            "alpha" | "a" => Channel::Alpha,
+            _ => Channel::Dev,
+        }
+    }
+
+    pub fn is_stable(&self) -> bool {
+        self.channel() == Channel::Stable
+    }
+}
+
+/// Release channel of a version, ordered from least to most stable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Channel {
+    /// Snapshots, nightlies and pre-releases without a recognised label, e.g. `1.0.0-next.3`
+    Dev,
+    Alpha,
+    Beta,
+    /// Maven milestones such as `6.0.0-M1`
+    Milestone,
+    Rc,
+    Stable,
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Channel::Dev => "dev",
+            Channel::Alpha => "alpha",
+            Channel::Beta => "beta",
+            Channel::Milestone => "milestone",
+            Channel::Rc => "rc",
+            Channel::Stable => "stable",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Channel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "dev" | "snapshot" | "nightly" => Ok(Channel::Dev),
+            "alpha" => Ok(Channel::Alpha),
+            "beta" => Ok(Channel::Beta),
+            "milestone" => Ok(Channel::Milestone),
+            "rc" => Ok(Channel::Rc),
+            "stable" => Ok(Channel::Stable),
+            _ => Err(format!(
+                "Unknown channel {s}, expected stable, rc, milestone, beta, alpha or dev"
+            )),
+        }
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.numbers.len().max(other.numbers.len());
+        for i in 0..len {
+            let a = self.numbers.get(i).copied().unwrap_or(0);
+            let b = other.numbers.get(i).copied().unwrap_or(0);
+            match a.cmp(&b) {
+                Ordering::Equal => {}
+                ord => return ord,
+            }
+        }
+
+        // A release sorts after any of its pre-releases
+        match (self.is_stable(), other.is_stable()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => compare_pre(
+                self.pre.as_deref().unwrap_or_default(),
+                other.pre.as_deref().unwrap_or_default(),
+            ),
+        }
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Compares pre-release identifiers the way semver does: numeric parts
+/// numerically, everything else lexically, shorter lists first on ties
+fn compare_pre(a: &str, b: &str) -> Ordering {
+    let mut left = a.split(['.', '-']);
+    let mut right = b.split(['.', '-']);
+    loop {
+        match (left.next(), right.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => {
+                let ord = match (x.parse::<u64>(), y.parse::<u64>()) {
+                    (Ok(x), Ok(y)) => x.cmp(&y),
+                    (Ok(_), Err(_)) => Ordering::Less,
+                    (Err(_), Ok(_)) => Ordering::Greater,
+                    (Err(_), Err(_)) => x.cmp(y),
+                };
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+        }
+    }
+}
+
+/// Compares two tags as versions, `None` when either has no version; identical tags are equal
+pub fn compare(a: &str, b: &str) -> Option<Ordering> {
+    if a == b {
+        return Some(Ordering::Equal);
+    }
+    Some(Version::parse(a)?.cmp(&Version::parse(b)?))
+}
+
+/// Checks whether a tag is a pre-release; tags without a version are not
+pub fn is_prerelease(tag: &str) -> bool {
+    Version::parse(tag).is_some_and(|version| !version.is_stable())
+}
+
+/// Picks the highest version among a list of tags, ignoring pre-releases and tags without a version
+pub fn highest_stable<S: AsRef<str>>(tags: &[S]) -> Option<&str> {
+    tags.iter()
+        .map(AsRef::as_ref)
+        .filter_map(|tag| Version::parse(tag).map(|version| (version, tag)))
+        .filter(|(version, _)| version.is_stable())
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, tag)| tag)
+}
+
+/// Picks the version to use from candidates: the only one, or the highest stable of many
+pub fn single_or_highest(candidates: &[String]) -> Option<String> {
+    match candidates {
+        [] => None,
+        [single] => Some(single.clone()),
+        many => highest_stable(many).map(str::to_string),
+    }
+}
+
+/// Picks the highest stable version tag, or the first tag when none carries a version; APIs
+/// often list tags by name, not by version
+pub fn highest_stable_tag(tags: &[String]) -> Option<String> {
+    if tags.iter().any(|tag| Version::parse(tag).is_some()) {
+        return highest_stable(tags).map(str::to_string);
+    }
+    tags.first().cloned()
+}
@@ -1,6 +1,8 @@
 use once_cell::sync::Lazy;
 use regex::Regex;
 
+use crate::cup_parser::CUP_COMMENT;
+
 /// Represents different formats used when replacing versions in text
 #[derive(Clone, Copy)]
 pub enum ReplacementFormat {
@@ -36,67 +38,67 @@ static VERSION_REPLACE_RE_3: Lazy<Regex> = Lazy::new(|| {
 
 // Pattern 4: "name:version" // comment
 static VERSION_REPLACE_RE_4: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r#"("(\w+):)([0-9]*\.?[0-9]+(?:\.[0-9]+)*)(")(\s*(?://|#).*)"#)
+    Regex::new(r#"("(\w+):)([0-9]*\.?[0-9]+(?:\.[0-9]+)*(?:-[0-9A-Za-z][0-9A-Za-z\.\-]*)?)(")(\s*(?://|#).*)"#)
         .expect("Failed to compile version replace regex 4")
 });
 
 // Pattern 5: "name": "version" // comment
 static VERSION_REPLACE_RE_5: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r#"("(\w+)":\s*")([0-9]*\.?[0-9]+(?:\.[0-9]+)*)(")(\s*(?://|#).*)"#)
+    Regex::new(r#"("(\w+)":\s*")([0-9]*\.?[0-9]+(?:\.[0-9]+)*(?:-[0-9A-Za-z][0-9A-Za-z\.\-]*)?)(")(\s*(?://|#).*)"#)
         .expect("Failed to compile version replace regex 5")
 });
 
 // Pattern 6: name = 'version' // comment (single quotes)
 static VERSION_REPLACE_RE_6: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"(\w+\s*=\s*')([0-9]*\.?[0-9]+(?:\.[0-9]+)*)(')(\s*(?://|#).*)")
+    Regex::new(r"(\w+\s*=\s*')([0-9]*\.?[0-9]+(?:\.[0-9]+)*(?:-[0-9A-Za-z][0-9A-Za-z\.\-]*)?)(')(\s*(?://|#).*)")
         .expect("Failed to compile version replace regex 6")
 });
 
 // Pattern 7: name := 'version' // comment (single quotes)
 static VERSION_REPLACE_RE_7: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"(\w+\s*:=\s*')([0-9]*\.?[0-9]+(?:\.[0-9]+)*)(')(\s*(?://|#).*)")
+    Regex::new(r"(\w+\s*:=\s*')([0-9]*\.?[0-9]+(?:\.[0-9]+)*(?:-[0-9A-Za-z][0-9A-Za-z\.\-]*)?)(')(\s*(?://|#).*)")
         .expect("Failed to compile version replace regex 7")
 });
 
 // Pattern 8: name: 'version' // comment (single quotes)
 static VERSION_REPLACE_RE_8: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"(\w+:\s*')([0-9]*\.?[0-9]+(?:\.[0-9]+)*)(')(\s*(?://|#).*)")
+    Regex::new(r"(\w+:\s*')([0-9]*\.?[0-9]+(?:\.[0-9]+)*(?:-[0-9A-Za-z][0-9A-Za-z\.\-]*)?)(')(\s*(?://|#).*)")
         .expect("Failed to compile version replace regex 8")
 });
 
 // Pattern 9: 'name:version' // comment (single quotes)
 static VERSION_REPLACE_RE_9: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"('(\w+):)([0-9]*\.?[0-9]+(?:\.[0-9]+)*)(')(\s*(?://|#).*)")
+    Regex::new(r"('(\w+):)([0-9]*\.?[0-9]+(?:\.[0-9]+)*(?:-[0-9A-Za-z][0-9A-Za-z\.\-]*)?)(')(\s*(?://|#).*)")
         .expect("Failed to compile version replace regex 9")
 });
 
 // Pattern 10: 'name': 'version' // comment (single quotes)
 static VERSION_REPLACE_RE_10: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"('(\w+)':\s*')([0-9]*\.?[0-9]+(?:\.[0-9]+)*)(')(\s*(?://|#).*)")
+    Regex::new(r"('(\w+)':\s*')([0-9]*\.?[0-9]+(?:\.[0-9]+)*(?:-[0-9A-Za-z][0-9A-Za-z\.\-]*)?)(')(\s*(?://|#).*)")
         .expect("Failed to compile version replace regex 10")
 });
 
 // Pattern 11: name = "version" // comment (double quotes)
 static VERSION_REPLACE_RE_11: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r#"(\w+\s*=\s*")([0-9]*\.?[0-9]+(?:\.[0-9]+)*)(")(\s*(?://|#).*)"#)
+    Regex::new(r#"(\w+\s*=\s*")([0-9]*\.?[0-9]+(?:\.[0-9]+)*(?:-[0-9A-Za-z][0-9A-Za-z\.\-]*)?)(")(\s*(?://|#).*)"#)
         .expect("Failed to compile version replace regex 11")
 });
 
 // Pattern 12: name := "version" // comment (double quotes)
 static VERSION_REPLACE_RE_12: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r#"(\w+\s*:=\s*")([0-9]*\.?[0-9]+(?:\.[0-9]+)*)(")(\s*(?://|#).*)"#)
+    Regex::new(r#"(\w+\s*:=\s*")([0-9]*\.?[0-9]+(?:\.[0-9]+)*(?:-[0-9A-Za-z][0-9A-Za-z\.\-]*)?)(")(\s*(?://|#).*)"#)
         .expect("Failed to compile version replace regex 12")
 });
 
 // Pattern 13: name: "version" // comment (double quotes)
 static VERSION_REPLACE_RE_13: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r#"(\w+:\s*")([0-9]*\.?[0-9]+(?:\.[0-9]+)*)(")(\s*(?://|#).*)"#)
+    Regex::new(r#"(\w+:\s*")([0-9]*\.?[0-9]+(?:\.[0-9]+)*(?:-[0-9A-Za-z][0-9A-Za-z\.\-]*)?)(")(\s*(?://|#).*)"#)
         .expect("Failed to compile version replace regex 13")
 });
 
 // Pattern 14: "name-version" = "version" // comment (JSON-like with dashes)
 static VERSION_REPLACE_RE_14: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r#"("[\w-]+"\s*=\s*")([0-9]*\.?[0-9]+(?:\.[0-9]+)*)(")(\s*(?://|#).*)"#)
+    Regex::new(r#"("[\w-]+"\s*=\s*")([0-9]*\.?[0-9]+(?:\.[0-9]+)*(?:-[0-9A-Za-z][0-9A-Za-z\.\-]*)?)(")(\s*(?://|#).*)"#)
         .expect("Failed to compile version replace regex 14")
 });
 
@@ -190,6 +192,15 @@ pub static VERSION_PATTERNS: &[VersionPattern] = &[
     },
 ];
 
+/// Splits a line at its cup marker, so versions are only looked for in the value and the comment
+/// opener before it; options such as `skip=1.3.0` after the marker are never read as the version
+fn split_annotation(line: &str) -> (&str, &str) {
+    match line.find(CUP_COMMENT) {
+        Some(pos) => line.split_at(pos),
+        None => (line, ""),
+    }
+}
+
 /// Attempts to replace the version in a line with a new version using all available patterns
 ///
 /// # Arguments
@@ -200,42 +211,91 @@ pub static VERSION_PATTERNS: &[VersionPattern] = &[
 /// * `Some(String)` containing the updated line if a pattern matches
 /// * `None` if no pattern matches for replacement
 pub fn try_replace_version_in_line(line: &str, new_version: &str) -> Option<String> {
+    let (line, annotation) = split_annotation(line);
     for pattern in VERSION_PATTERNS {
         if pattern.replace_regex.is_match(line) {
-            return Some(
-                pattern
-                    .replace_regex
-                    .replace_all(line, |caps: &regex::Captures| {
-                        match pattern.replacement_format {
-                            ReplacementFormat::Simple => {
-                                format!("{}{}{}", &caps[1], new_version, &caps[3])
-                            }
-                            ReplacementFormat::Extended => {
-                                format!("{}{}{}{}", &caps[1], new_version, &caps[4], &caps[5])
-                            }
-                            ReplacementFormat::Quoted => {
-                                format!("{}{}{}{}", &caps[1], new_version, &caps[3], &caps[4])
-                            }
+            let replaced = pattern
+                .replace_regex
+                .replace_all(line, |caps: &regex::Captures| {
+                    match pattern.replacement_format {
+                        ReplacementFormat::Simple => {
+                            format!("{}{}{}", &caps[1], new_version, &caps[3])
+                        }
+                        ReplacementFormat::Extended => {
+                            format!("{}{}{}{}", &caps[1], new_version, &caps[4], &caps[5])
+                        }
+                        ReplacementFormat::Quoted => {
+                            format!("{}{}{}{}", &caps[1], new_version, &caps[3], &caps[4])
                         }
-                    })
-                    .to_string(),
-            );
+                    }
+                });
+            return Some(format!("{replaced}{annotation}"));
         }
     }
     None
 }
 
-/// Cleans version tags by removing 'v' or 'V' prefixes
+/// Extracts the version currently written in a line, using the same patterns as replacement
 ///
 /// # Arguments
-/// * `inp` - The input version string to clean
+/// * `line` - The line of text to read the version from
 ///
 /// # Returns
-/// * A cleaned version string with 'v' or 'V' prefixes removed
-pub fn clean_tag(inp: String) -> String {
-    if inp.starts_with(['V', 'v']) {
-        inp.replace("v", "").replace("V", "")
-    } else {
-        inp
+/// * `Some(String)` containing the current version if a pattern matches
+/// * `None` if no pattern matches
+pub fn extract_version_from_line(line: &str) -> Option<String> {
+    let (line, _) = split_annotation(line);
+    VERSION_PATTERNS.iter().find_map(|pattern| {
+        let caps = pattern.replace_regex.captures(line)?;
+        let version = match pattern.replacement_format {
+            ReplacementFormat::Simple | ReplacementFormat::Quoted => caps.get(2),
+            ReplacementFormat::Extended => caps.get(3),
+        };
+        version.map(|m| m.as_str().to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_after_the_marker_are_not_the_version() {
+        let lines = [
+            r#"lib: "1.0.0" # [cup] Git file:///repo skip=1.3.0 # 1.3.0 is broken"#,
+            r#"lib = "1.0.0" # [cup] GitHub o/r update=minor # stay on 1.x"#,
+            r#"lib = "1.0.0" // [cup] GitHub o/r constraint=^1 # no 2.x yet"#,
+        ];
+        for line in lines {
+            assert_eq!(extract_version_from_line(line).as_deref(), Some("1.0.0"));
+        }
+    }
+
+    #[test]
+    fn replacement_leaves_the_annotation_untouched() {
+        assert_eq!(
+            try_replace_version_in_line(
+                r#"lib: "1.0.0" # [cup] Git file:///repo skip=1.3.0 # 1.3.0 is broken"#,
+                "1.2.0"
+            )
+            .as_deref(),
+            Some(r#"lib: "1.2.0" # [cup] Git file:///repo skip=1.3.0 # 1.3.0 is broken"#)
+        );
+        assert_eq!(
+            try_replace_version_in_line(
+                "lib = 1.0.0 # [cup] GitHub o/r update=minor # stay on 1.x",
+                "1.4.0"
+            )
+            .as_deref(),
+            Some("lib = 1.4.0 # [cup] GitHub o/r update=minor # stay on 1.x")
+        );
+        assert_eq!(
+            try_replace_version_in_line(
+                r#"lib = "1.0.0" // [cup] GitHub o/r constraint=^1 # no 2.x yet"#,
+                "1.9.0"
+            )
+            .as_deref(),
+            Some(r#"lib = "1.9.0" // [cup] GitHub o/r constraint=^1 # no 2.x yet"#)
+        );
     }
 }
@@ -0,0 +1,191 @@
+use jiff::Timestamp;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::{
+    command::ExternalCommandSource,
+    crates::CratesSource,
+    cup_parser::FileTarget,
+    file_source::FileSource,
+    git::GitSource,
+    github::GitHubSource,
+    gitlab::GitLabSource,
+    goproxy::GoProxySource,
+    helm::HelmSource,
+    http_json::HttpJsonSource,
+    init::{Config, Remote},
+    maven::MavenSource,
+    npm::NpmSource,
+    nuget::NuGetSource,
+    oci::OciSource,
+    packagist::PackagistSource,
+    plugin::PluginProvider,
+    pypi::PyPISource,
+    rubygems::RubyGemsSource,
+    terraform::TerraformSource,
+    version::{self, Channel, Version},
+};
+
+/// A listed tag with the time it was published, when the source records one
+#[derive(Debug, Clone)]
+pub struct Release {
+    pub tag: String,
+    pub published: Option<Timestamp>,
+}
+
+impl Release {
+    /// Creates a release from an RFC 3339 publish time such as `2024-05-01T12:00:00Z`,
+    /// leaving the time unknown when it does not parse
+    pub fn new(tag: String, published: Option<&str>) -> Self {
+        Release {
+            tag,
+            published: published.and_then(|time| time.parse().ok()),
+        }
+    }
+}
+
+/// A place versions for an annotated target can be looked up from
+pub trait VersionSource: Send + Sync {
+    /// Describes where versions for the target come from, e.g. `GitHub repository owner/repo`
+    fn describe(&self, target: &FileTarget) -> String;
+
+    /// Lists every candidate version the source offers for the target, as published
+    fn list_versions(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>>;
+
+    /// Lists candidate versions with their publish times, unknown unless the source records them
+    fn list_releases(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+        Ok(self
+            .list_versions(target, config)?
+            .into_iter()
+            .map(|tag| Release::new(tag, None))
+            .collect())
+    }
+
+    /// Lists releases with their publish times together with the version `latest` would pick
+    /// among them, from a single lookup: by default the only release, or the highest stable one
+    fn list_releases_and_latest(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<(Vec<Release>, Option<String>), Box<dyn std::error::Error>> {
+        let releases = self.list_releases(target, config)?;
+        let latest = match releases.as_slice() {
+            [single] => Some(single.tag.clone()),
+            many => many
+                .iter()
+                .filter(|release| {
+                    let text = self.version_of(target, &release.tag);
+                    self.channel(text) == Some(Channel::Stable)
+                })
+                .max_by(|a, b| {
+                    let (a, b) = (
+                        self.version_of(target, &a.tag),
+                        self.version_of(target, &b.tag),
+                    );
+                    self.compare(a, b).unwrap_or(Ordering::Equal)
+                })
+                .map(|release| release.tag.clone()),
+        };
+        Ok((releases, latest))
+    }
+
+    /// Returns the part of a listed tag that compares as a version, e.g. without an image variant suffix
+    fn version_of<'a>(&self, _target: &FileTarget, tag: &'a str) -> &'a str {
+        tag
+    }
+
+    /// Compares two versions the way the source orders them, `None` when either is not a version
+    fn compare(&self, a: &str, b: &str) -> Option<Ordering> {
+        version::compare(a, b)
+    }
+
+    /// Release channel of a version as the source classifies it, `None` when it is not a version
+    fn channel(&self, version: &str) -> Option<Channel> {
+        Version::parse(version).map(|version| version.channel())
+    }
+
+    /// Fetches the version to update to, by default the highest stable candidate
+    fn latest(
+        &self,
+        target: &FileTarget,
+        config: &Config,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let candidates = self.list_versions(target, config)?;
+        version::highest_stable(&candidates)
+            .map(str::to_string)
+            .ok_or_else(|| format!("No stable versions found for {}", self.describe(target)).into())
+    }
+}
+
+/// Version sources keyed by the remote type annotations name
+pub struct SourceRegistry {
+    sources: HashMap<Remote, Box<dyn VersionSource>>,
+}
+
+impl SourceRegistry {
+    /// Creates a registry without any sources
+    pub fn new() -> Self {
+        SourceRegistry {
+            sources: HashMap::new(),
+        }
+    }
+
+    /// Creates a registry serving every remote cup ships with
+    pub fn builtin() -> Self {
+        let mut registry = SourceRegistry::new();
+        registry.register(Remote::GitHub, GitHubSource);
+        registry.register(Remote::GitLab, GitLabSource);
+        registry.register(Remote::Git, GitSource);
+        registry.register(Remote::Oci, OciSource);
+        registry.register(Remote::Crates, CratesSource);
+        registry.register(Remote::Npm, NpmSource);
+        registry.register(Remote::PyPI, PyPISource);
+        registry.register(Remote::Maven, MavenSource);
+        registry.register(Remote::GoProxy, GoProxySource);
+        registry.register(Remote::Helm, HelmSource);
+        registry.register(Remote::Terraform, TerraformSource);
+        registry.register(Remote::RubyGems, RubyGemsSource);
+        registry.register(Remote::NuGet, NuGetSource);
+        registry.register(Remote::Packagist, PackagistSource);
+        registry.register(Remote::Http, HttpJsonSource);
+        registry.register(Remote::Command, ExternalCommandSource);
+        registry.register(Remote::File, FileSource);
+        registry
+    }
+
+    /// Creates the built-in registry plus a source for every plugin configured in `cup.toml`
+    pub fn with_plugins(config: &Config) -> Self {
+        let mut registry = SourceRegistry::builtin();
+        for name in config.plugin.keys() {
+            registry.register(Remote::Plugin(name.clone()), PluginProvider::new(name));
+        }
+        registry
+    }
+
+    /// Registers a source for a remote, replacing any source registered before
+    pub fn register(&mut self, remote: Remote, source: impl VersionSource + 'static) {
+        self.sources.insert(remote, Box::new(source));
+    }
+
+    /// Returns the source serving a remote
+    pub fn get(&self, remote: &Remote) -> Result<&dyn VersionSource, Box<dyn std::error::Error>> {
+        self.sources
+            .get(remote)
+            .map(|source| source.as_ref())
+            .ok_or_else(|| format!("No version source registered for {remote:?}").into())
+    }
+}
+
+impl Default for SourceRegistry {
+    fn default() -> Self {
+        SourceRegistry::builtin()
+    }
+}